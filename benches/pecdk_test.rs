@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use paired::bls12_381::{Bls12, Fr};
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use rust_searchable_pke::pecdk::*;
+
+fn bench_pecdk_test(c: &mut Criterion) {
+    let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let n = 32;
+    let keywords = (0..n as u32)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+    let public_key = secret_key.into_public_key();
+    let ct = public_key
+        .encrypt::<_, Fr>(keywords.clone(), &mut rng)
+        .unwrap();
+    // An AND search can only stop once fewer than m slots remain, so it needs m close to n.
+    let absent = (n as u32..(n + n / 2) as u32)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let cases = [
+        ("or_early_match", keywords[0..4].to_vec(), SearchSym::OR),
+        ("and_non_match", absent, SearchSym::AND),
+    ];
+
+    let mut group = c.benchmark_group("pecdk_test");
+    group.sample_size(10);
+    for (name, td_keywords, sym) in cases {
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(td_keywords, sym, &mut rng)
+            .unwrap();
+        group.bench_with_input(BenchmarkId::new("early_exit", name), &ct, |b, ct| {
+            b.iter(|| trapdoor.test(ct).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("all_slots", name), &ct, |b, ct| {
+            b.iter(|| trapdoor.count_matches(ct).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pecdk_test);
+criterion_main!(benches);
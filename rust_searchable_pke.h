@@ -7,80 +7,425 @@
 #include <stdlib.h>
 
 
-typedef struct CPecdkCiphertext {
+#define NGRAM_SIZE 3
+
+/**
+ * Number of PBKDF2-HMAC-SHA256 iterations used by `seed_from_passphrase`.
+ * Changing it changes every key derived from a passphrase.
+ */
+#define PASSPHRASE_ITERATIONS 100000
+
+#define SEED_SIZE 32
+
+/**
+ * Result of a C API call.
+ * When it is not `Success`, `lastErrorMessage()` describes the failure
+ * and the output argument is left untouched.
+ */
+typedef enum CErrorCode {
+  CErrorCode_Success = 0,
+  CErrorCode_NullPointer = 1,
+  CErrorCode_InvalidUtf8 = 2,
+  CErrorCode_InvalidArgument = 3,
+  CErrorCode_InvalidObject = 4,
+  CErrorCode_SerializeFailure = 5,
+  CErrorCode_CryptoFailure = 6,
+  CErrorCode_Panic = 7,
+  CErrorCode_CurveMismatch = 8,
+} CErrorCode;
+
+typedef struct CDpeksServerPublicKey {
   char *ptr;
-} CPecdkCiphertext;
+} CDpeksServerPublicKey;
+
+typedef struct CDpeksPublicKey {
+  char *ptr;
+} CDpeksPublicKey;
+
+typedef struct CDpeksCiphertext {
+  char *ptr;
+} CDpeksCiphertext;
+
+typedef struct CDpeksSecretKey {
+  char *ptr;
+} CDpeksSecretKey;
+
+typedef struct CDpeksServerSecretKey {
+  char *ptr;
+} CDpeksServerSecretKey;
+
+typedef struct CDpeksTrapdoor {
+  char *ptr;
+} CDpeksTrapdoor;
 
 typedef struct CPecdkPublicKey {
   char *ptr;
 } CPecdkPublicKey;
 
-typedef struct CPecdkTrapdoor {
+typedef struct CPecdkCiphertext {
   char *ptr;
-} CPecdkTrapdoor;
+} CPecdkCiphertext;
 
 typedef struct CPecdkSecretKey {
   char *ptr;
 } CPecdkSecretKey;
 
-struct CPecdkCiphertext genCiphertextForFieldSearch(struct CPecdkPublicKey public_key,
-                                                    char *region_name,
-                                                    size_t num_fields,
-                                                    char **fields,
-                                                    char **vals);
+typedef struct CPecdkTrapdoor {
+  char *ptr;
+} CPecdkTrapdoor;
+
+typedef struct CPaeksSecretKey {
+  char *ptr;
+} CPaeksSecretKey;
+
+typedef struct CPaeksPublicKey {
+  char *ptr;
+} CPaeksPublicKey;
+
+typedef struct CPaeksCiphertext {
+  char *ptr;
+} CPaeksCiphertext;
+
+typedef struct CPaeksTrapdoor {
+  char *ptr;
+} CPaeksTrapdoor;
+
+typedef struct CSeed {
+  uint8_t bytes[SEED_SIZE];
+} CSeed;
+
+typedef struct CPeksPublicKey {
+  char *ptr;
+} CPeksPublicKey;
+
+typedef struct CPeksCiphertext {
+  char *ptr;
+} CPeksCiphertext;
+
+typedef struct CPeksSecretKey {
+  char *ptr;
+} CPeksSecretKey;
+
+typedef struct CPeksTrapdoor {
+  char *ptr;
+} CPeksTrapdoor;
+
+uint8_t curveId(void);
+
+enum CErrorCode dpeksEncryptKeyword(struct CDpeksServerPublicKey server_public_key,
+                                    struct CDpeksPublicKey public_key,
+                                    char *keyword,
+                                    struct CDpeksCiphertext *ciphertext);
+
+void dpeksFreeCiphertext(struct CDpeksCiphertext ciphertext);
+
+void dpeksFreePublicKey(struct CDpeksPublicKey public_key);
+
+/**
+ * Overwrites the serialized secret key with zeros before freeing it.
+ */
+void dpeksFreeSecretKey(struct CDpeksSecretKey secret_key);
+
+void dpeksFreeServerPublicKey(struct CDpeksServerPublicKey server_public_key);
+
+/**
+ * Overwrites the serialized secret key with zeros before freeing it.
+ */
+void dpeksFreeServerSecretKey(struct CDpeksServerSecretKey server_secret_key);
+
+void dpeksFreeTrapdoor(struct CDpeksTrapdoor trapdoor);
+
+enum CErrorCode dpeksGenPublicKey(struct CDpeksSecretKey secret_key,
+                                  struct CDpeksPublicKey *public_key);
 
-struct CPecdkCiphertext genCiphertextForPrefixSearch(struct CPecdkPublicKey public_key,
+enum CErrorCode dpeksGenSecretKey(struct CDpeksSecretKey *secret_key);
+
+enum CErrorCode dpeksGenServerPublicKey(struct CDpeksServerSecretKey server_secret_key,
+                                        struct CDpeksServerPublicKey *server_public_key);
+
+enum CErrorCode dpeksGenServerSecretKey(struct CDpeksServerSecretKey *server_secret_key);
+
+enum CErrorCode dpeksGenTrapdoor(struct CDpeksServerPublicKey server_public_key,
+                                 struct CDpeksSecretKey secret_key,
+                                 char *keyword,
+                                 struct CDpeksTrapdoor *trapdoor);
+
+enum CErrorCode dpeksTest(struct CDpeksServerSecretKey server_secret_key,
+                          struct CDpeksCiphertext ciphertext,
+                          struct CDpeksTrapdoor trapdoor,
+                          bool *result);
+
+enum CErrorCode genCiphertextForFieldSearch(struct CPecdkPublicKey public_key,
+                                            char *region_name,
+                                            size_t num_fields,
+                                            char **fields,
+                                            char **vals,
+                                            struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode genCiphertextForFieldSearchBytes(struct CPecdkPublicKey public_key,
+                                                 char *region_name,
+                                                 size_t num_fields,
+                                                 const uint8_t *const *fields,
+                                                 const size_t *field_lens,
+                                                 const uint8_t *const *vals,
+                                                 const size_t *val_lens,
+                                                 struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode genCiphertextForGraphemePrefixSearch(struct CPecdkPublicKey public_key,
                                                      char *region_name,
-                                                     char *string);
+                                                     char *string,
+                                                     struct CPecdkCiphertext *ciphertext);
 
-struct CPecdkCiphertext genCiphertextForRangeSearch(struct CPecdkPublicKey public_key,
-                                                    char *region_name,
-                                                    size_t bit_size,
-                                                    unsigned int val);
+enum CErrorCode genCiphertextForPrefixSearch(struct CPecdkPublicKey public_key,
+                                             char *region_name,
+                                             char *string,
+                                             struct CPecdkCiphertext *ciphertext);
 
-struct CPecdkTrapdoor genTrapdoorForFieldAndSearch(struct CPecdkSecretKey secret_key,
-                                                   char *region_name,
-                                                   size_t num_fields,
-                                                   char **fields,
-                                                   char **vals);
+enum CErrorCode genCiphertextForRangeSearch(struct CPecdkPublicKey public_key,
+                                            char *region_name,
+                                            size_t bit_size,
+                                            unsigned int val,
+                                            struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode genCiphertextForSubstringSearch(struct CPecdkPublicKey public_key,
+                                                char *region_name,
+                                                char *string,
+                                                struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode genCiphertextForSuffixSearch(struct CPecdkPublicKey public_key,
+                                             char *region_name,
+                                             char *string,
+                                             struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode genTrapdoorForFieldAndSearch(struct CPecdkSecretKey secret_key,
+                                             char *region_name,
+                                             size_t num_fields,
+                                             char **fields,
+                                             char **vals,
+                                             struct CPecdkTrapdoor *trapdoor);
 
-struct CPecdkTrapdoor genTrapdoorForFieldOrSearch(struct CPecdkSecretKey secret_key,
+enum CErrorCode genTrapdoorForFieldAndSearchBytes(struct CPecdkSecretKey secret_key,
                                                   char *region_name,
                                                   size_t num_fields,
-                                                  char **fields,
-                                                  char **vals);
+                                                  const uint8_t *const *fields,
+                                                  const size_t *field_lens,
+                                                  const uint8_t *const *vals,
+                                                  const size_t *val_lens,
+                                                  struct CPecdkTrapdoor *trapdoor);
 
-struct CPecdkTrapdoor genTrapdoorForPrefixSearch(struct CPecdkSecretKey secret_key,
+enum CErrorCode genTrapdoorForFieldOrSearch(struct CPecdkSecretKey secret_key,
+                                            char *region_name,
+                                            size_t num_fields,
+                                            char **fields,
+                                            char **vals,
+                                            struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode genTrapdoorForFieldOrSearchBytes(struct CPecdkSecretKey secret_key,
                                                  char *region_name,
-                                                 char *prefix);
+                                                 size_t num_fields,
+                                                 const uint8_t *const *fields,
+                                                 const size_t *field_lens,
+                                                 const uint8_t *const *vals,
+                                                 const size_t *val_lens,
+                                                 struct CPecdkTrapdoor *trapdoor);
+
+/**
+ * The prefix is given as bytes and rejected with `InvalidArgument`
+ * unless it is valid UTF-8 ending on a character boundary.
+ */
+enum CErrorCode genTrapdoorForGraphemePrefixSearch(struct CPecdkSecretKey secret_key,
+                                                   char *region_name,
+                                                   const uint8_t *prefix,
+                                                   size_t prefix_len,
+                                                   struct CPecdkTrapdoor *trapdoor);
 
-struct CPecdkTrapdoor genTrapdoorForPrefixSearchExact(struct CPecdkSecretKey secret_key,
-                                                      char *region_name,
-                                                      char *string);
+enum CErrorCode genTrapdoorForGraphemePrefixSearchExact(struct CPecdkSecretKey secret_key,
+                                                        char *region_name,
+                                                        char *string,
+                                                        struct CPecdkTrapdoor *trapdoor);
 
-struct CPecdkTrapdoor genTrapdoorForRangeSearch(struct CPecdkSecretKey secret_key,
+enum CErrorCode genTrapdoorForPrefixSearch(struct CPecdkSecretKey secret_key,
+                                           char *region_name,
+                                           char *prefix,
+                                           struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode genTrapdoorForPrefixSearchExact(struct CPecdkSecretKey secret_key,
                                                 char *region_name,
-                                                unsigned int min,
-                                                unsigned int max,
-                                                size_t bit_size);
+                                                char *string,
+                                                struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode genTrapdoorForRangeSearch(struct CPecdkSecretKey secret_key,
+                                          char *region_name,
+                                          unsigned int min,
+                                          unsigned int max,
+                                          size_t bit_size,
+                                          struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode genTrapdoorForSubstringSearch(struct CPecdkSecretKey secret_key,
+                                              char *region_name,
+                                              char *substring,
+                                              struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode genTrapdoorForSuffixSearch(struct CPecdkSecretKey secret_key,
+                                           char *region_name,
+                                           char *suffix,
+                                           struct CPecdkTrapdoor *trapdoor);
+
+/**
+ * Returns the message of the last failed call on the current thread, or NULL.
+ * The string is owned by the library and stays valid until the next call on the same thread.
+ */
+const char *lastErrorMessage(void);
+
+enum CErrorCode paeksEncryptKeyword(struct CPaeksSecretKey sender_secret_key,
+                                    struct CPaeksPublicKey receiver_public_key,
+                                    char *keyword,
+                                    struct CPaeksCiphertext *ciphertext);
+
+void paeksFreeCiphertext(struct CPaeksCiphertext ciphertext);
+
+void paeksFreePublicKey(struct CPaeksPublicKey public_key);
+
+/**
+ * Overwrites the serialized secret key with zeros before freeing it.
+ */
+void paeksFreeSecretKey(struct CPaeksSecretKey secret_key);
 
-struct CPecdkCiphertext pecdkEncryptKeyword(struct CPecdkPublicKey public_key, char **keywords);
+void paeksFreeTrapdoor(struct CPaeksTrapdoor trapdoor);
+
+enum CErrorCode paeksGenPublicKey(struct CPaeksSecretKey secret_key,
+                                  struct CPaeksPublicKey *public_key);
+
+enum CErrorCode paeksGenSecretKey(struct CPaeksSecretKey *secret_key);
+
+enum CErrorCode paeksGenTrapdoor(struct CPaeksSecretKey receiver_secret_key,
+                                 struct CPaeksPublicKey sender_public_key,
+                                 char *keyword,
+                                 struct CPaeksTrapdoor *trapdoor);
+
+enum CErrorCode paeksTest(struct CPaeksCiphertext ciphertext,
+                          struct CPaeksTrapdoor trapdoor,
+                          bool *result);
+
+enum CErrorCode pecdkEncryptKeyword(struct CPecdkPublicKey public_key,
+                                    char **keywords,
+                                    size_t num_keyword,
+                                    struct CPecdkCiphertext *ciphertext);
+
+/**
+ * Encrypts `keywords` so that they only match trapdoors naming the sender of `sender_secret_key`.
+ */
+enum CErrorCode pecdkEncryptKeywordAuthenticated(struct CPecdkPublicKey public_key,
+                                                 struct CPaeksSecretKey sender_secret_key,
+                                                 struct CPaeksPublicKey receiver_public_key,
+                                                 char **keywords,
+                                                 size_t num_keyword,
+                                                 struct CPecdkCiphertext *ciphertext);
+
+enum CErrorCode pecdkEncryptKeywordBytes(struct CPecdkPublicKey public_key,
+                                         const uint8_t *const *keywords,
+                                         const size_t *keyword_lens,
+                                         size_t num_keyword,
+                                         struct CPecdkCiphertext *ciphertext);
 
 void pecdkFreeCiphertext(struct CPecdkCiphertext ciphertext);
 
 void pecdkFreePublicKey(struct CPecdkPublicKey public_key);
 
+/**
+ * Overwrites the serialized secret key with zeros before freeing it.
+ */
 void pecdkFreeSecretKey(struct CPecdkSecretKey secret_key);
 
 void pecdkFreeTrapdoor(struct CPecdkTrapdoor trapdoor);
 
-struct CPecdkPublicKey pecdkGenPublicKey(struct CPecdkSecretKey secret_key);
+enum CErrorCode pecdkGenPublicKey(struct CPecdkSecretKey secret_key,
+                                  struct CPecdkPublicKey *public_key);
+
+enum CErrorCode pecdkGenSecretKey(size_t num_keyword, struct CPecdkSecretKey *secret_key);
+
+enum CErrorCode pecdkGenSecretKeyFromSeed(struct CSeed seed,
+                                          size_t num_keyword,
+                                          struct CPecdkSecretKey *secret_key);
+
+enum CErrorCode pecdkGenThresholdTrapdoor(struct CPecdkSecretKey secret_key,
+                                          char **keywords,
+                                          size_t num_keyword,
+                                          size_t threshold,
+                                          struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode pecdkGenThresholdTrapdoorBytes(struct CPecdkSecretKey secret_key,
+                                               const uint8_t *const *keywords,
+                                               const size_t *keyword_lens,
+                                               size_t num_keyword,
+                                               size_t threshold,
+                                               struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode pecdkGenTrapdoor(struct CPecdkSecretKey secret_key,
+                                 char **keywords,
+                                 size_t num_keyword,
+                                 int sym,
+                                 struct CPecdkTrapdoor *trapdoor);
+
+/**
+ * Generates a trapdoor matching only ciphertexts from the sender of `sender_public_key`.
+ */
+enum CErrorCode pecdkGenTrapdoorAuthenticated(struct CPecdkSecretKey secret_key,
+                                              struct CPaeksSecretKey receiver_secret_key,
+                                              struct CPaeksPublicKey sender_public_key,
+                                              char **keywords,
+                                              size_t num_keyword,
+                                              int sym,
+                                              struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode pecdkGenTrapdoorBytes(struct CPecdkSecretKey secret_key,
+                                      const uint8_t *const *keywords,
+                                      const size_t *keyword_lens,
+                                      size_t num_keyword,
+                                      int sym,
+                                      struct CPecdkTrapdoor *trapdoor);
+
+enum CErrorCode pecdkTest(struct CPecdkCiphertext ciphertext,
+                          struct CPecdkTrapdoor trapdoor,
+                          bool *result);
+
+enum CErrorCode peksEncryptKeyword(struct CPeksPublicKey public_key,
+                                   char *keyword,
+                                   struct CPeksCiphertext *ciphertext);
+
+void peksFreeCiphertext(struct CPeksCiphertext ciphertext);
+
+void peksFreePublicKey(struct CPeksPublicKey public_key);
+
+/**
+ * Overwrites the serialized secret key with zeros before freeing it.
+ */
+void peksFreeSecretKey(struct CPeksSecretKey secret_key);
+
+void peksFreeTrapdoor(struct CPeksTrapdoor trapdoor);
+
+enum CErrorCode peksGenPublicKey(struct CPeksSecretKey secret_key,
+                                 struct CPeksPublicKey *public_key);
+
+/**
+ * Writes the public key paired with `peksGenSecretKeyFromSeed` for the same seed.
+ */
+enum CErrorCode peksGenPublicKeyFromSeed(struct CSeed seed, struct CPeksPublicKey *public_key);
+
+enum CErrorCode peksGenSecretKey(struct CPeksSecretKey *secret_key);
+
+enum CErrorCode peksGenSecretKeyFromSeed(struct CSeed seed, struct CPeksSecretKey *secret_key);
 
-struct CPecdkSecretKey pecdkGenSecretKey(size_t num_keyword);
+enum CErrorCode peksGenTrapdoor(struct CPeksSecretKey secret_key,
+                                char *keyword,
+                                struct CPeksTrapdoor *trapdoor);
 
-struct CPecdkTrapdoor pecdkGenTrapdoor(struct CPecdkSecretKey secret_key,
-                                       char **keywords,
-                                       size_t num_keyword,
-                                       int sym);
+enum CErrorCode peksTest(struct CPeksCiphertext ciphertext,
+                         struct CPeksTrapdoor trapdoor,
+                         bool *result);
 
-int pecdkTest(struct CPecdkCiphertext ciphertext, struct CPecdkTrapdoor trapdoor);
+enum CErrorCode seedFromPassphrase(const uint8_t *passphrase,
+                                   size_t passphrase_len,
+                                   const uint8_t *salt,
+                                   size_t salt_len,
+                                   struct CSeed *seed);
@@ -0,0 +1,422 @@
+use super::fields::{Fq, Fq12, Fq2, Fr, FrRepr};
+use super::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ff::{BigInt, One};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+use fff::Field;
+use groupy::{CurveAffine, CurveProjective, EncodedPoint, GroupDecodingError};
+use paired::PairingCurveAffine;
+use rand_core::RngCore;
+use serde::de::{Error as DeserializeError, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::BorrowMut;
+use std::{fmt, marker::PhantomData};
+
+fn decode<P: SWCurveConfig>(
+    mut bytes: &[u8],
+    compress: Compress,
+    check_subgroup: bool,
+) -> Result<Affine<P>, GroupDecodingError> {
+    let point =
+        Affine::<P>::deserialize_with_mode(&mut bytes, compress, Validate::No).map_err(|err| {
+            match err {
+                SerializationError::UnexpectedFlags => {
+                    GroupDecodingError::UnexpectedCompressionMode
+                }
+                // A compressed x without a matching y on the curve also ends up here.
+                SerializationError::InvalidData => GroupDecodingError::NotOnCurve,
+                _ => GroupDecodingError::UnexpectedInformation,
+            }
+        })?;
+    if !point.is_on_curve() {
+        return Err(GroupDecodingError::NotOnCurve);
+    }
+    if check_subgroup && !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(GroupDecodingError::NotInSubgroup);
+    }
+    Ok(point)
+}
+
+macro_rules! encoded_point {
+    ($name:ident, $affine:ident, $size:expr, $compress:expr) => {
+        #[derive(Copy, Clone)]
+        pub struct $name([u8; $size]);
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsMut<[u8]> for $name {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+
+        impl EncodedPoint for $name {
+            type Affine = $affine;
+
+            fn empty() -> Self {
+                $name([0u8; $size])
+            }
+
+            fn size() -> usize {
+                $size
+            }
+
+            fn into_affine(&self) -> Result<$affine, GroupDecodingError> {
+                decode(&self.0, $compress, true).map($affine)
+            }
+
+            fn into_affine_unchecked(&self) -> Result<$affine, GroupDecodingError> {
+                decode(&self.0, $compress, false).map($affine)
+            }
+
+            fn from_affine(affine: $affine) -> Self {
+                let mut bytes = [0u8; $size];
+                affine
+                    .0
+                    .serialize_with_mode(&mut bytes[..], $compress)
+                    .expect("the buffer has the encoded size");
+                $name(bytes)
+            }
+        }
+    };
+}
+
+macro_rules! curve {
+    (
+        $projective:ident,
+        $affine:ident,
+        $compressed:ident,
+        $uncompressed:ident,
+        $prepared:ident,
+        $base:ident,
+        $ark_projective:ty,
+        $ark_affine:ty,
+        $ark_prepared:ty,
+        $compressed_size:expr
+    ) => {
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        pub struct $projective(pub(crate) $ark_projective);
+
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        pub struct $affine(pub(crate) $ark_affine);
+
+        #[derive(Clone, Debug)]
+        pub struct $prepared(pub(crate) $ark_prepared);
+
+        encoded_point!($compressed, $affine, $compressed_size, Compress::Yes);
+        encoded_point!($uncompressed, $affine, 2 * $compressed_size, Compress::No);
+
+        impl From<$ark_projective> for $projective {
+            fn from(point: $ark_projective) -> Self {
+                $projective(point)
+            }
+        }
+
+        impl From<$projective> for $ark_projective {
+            fn from(point: $projective) -> Self {
+                point.0
+            }
+        }
+
+        impl From<$ark_affine> for $affine {
+            fn from(point: $ark_affine) -> Self {
+                $affine(point)
+            }
+        }
+
+        impl From<$affine> for $ark_affine {
+            fn from(point: $affine) -> Self {
+                point.0
+            }
+        }
+
+        impl From<$affine> for $projective {
+            fn from(point: $affine) -> Self {
+                $projective(point.0.into_group())
+            }
+        }
+
+        impl From<$projective> for $affine {
+            fn from(point: $projective) -> Self {
+                $affine(point.0.into_affine())
+            }
+        }
+
+        impl CurveProjective for $projective {
+            type Engine = Bn254;
+            type Scalar = Fr;
+            type Base = $base;
+            type Affine = $affine;
+
+            fn random<R: RngCore>(rng: &mut R) -> Self {
+                let mut point = Self::one();
+                point.mul_assign(Fr::random(rng));
+                point
+            }
+
+            fn zero() -> Self {
+                $projective(<$ark_projective>::default())
+            }
+
+            fn one() -> Self {
+                $projective(<$ark_projective>::generator())
+            }
+
+            fn is_zero(&self) -> bool {
+                self.0 == <$ark_projective>::default()
+            }
+
+            fn batch_normalization<S: BorrowMut<Self>>(v: &mut [S]) {
+                let points = v.iter_mut().map(|p| p.borrow_mut().0).collect::<Vec<_>>();
+                let normalized = <$ark_projective>::normalize_batch(&points);
+                for (p, affine) in v.iter_mut().zip(normalized) {
+                    p.borrow_mut().0 = affine.into_group();
+                }
+            }
+
+            fn is_normalized(&self) -> bool {
+                self.is_zero() || self.0.z.is_one()
+            }
+
+            fn double(&mut self) {
+                self.0.double_in_place();
+            }
+
+            fn add_assign(&mut self, other: &Self) {
+                self.0 += other.0;
+            }
+
+            fn add_assign_mixed(&mut self, other: &$affine) {
+                self.0 += other.0;
+            }
+
+            fn negate(&mut self) {
+                self.0 = -self.0;
+            }
+
+            fn mul_assign<S: Into<FrRepr>>(&mut self, other: S) {
+                self.0 = self.0.mul_bigint(BigInt(other.into().0));
+            }
+
+            fn into_affine(&self) -> $affine {
+                $affine(self.0.into_affine())
+            }
+
+            fn recommended_wnaf_for_scalar(scalar: FrRepr) -> usize {
+                let num_bits = fff::PrimeFieldRepr::num_bits(&scalar) as usize;
+                if num_bits >= 130 {
+                    4
+                } else if num_bits >= 34 {
+                    3
+                } else {
+                    2
+                }
+            }
+
+            fn recommended_wnaf_for_num_scalars(num_scalars: usize) -> usize {
+                const RECOMMENDATIONS: [usize; 12] =
+                    [1, 3, 7, 20, 43, 120, 273, 563, 1630, 3128, 7933, 62569];
+                RECOMMENDATIONS
+                    .iter()
+                    .take_while(|r| num_scalars >= **r)
+                    .count()
+                    + 2
+            }
+
+            fn hash(msg: &[u8]) -> Self {
+                Self::hash_to_curve(msg, super::hash::DST)
+            }
+        }
+
+        impl CurveAffine for $affine {
+            type Engine = Bn254;
+            type Scalar = Fr;
+            type Base = $base;
+            type Projective = $projective;
+            type Uncompressed = $uncompressed;
+            type Compressed = $compressed;
+
+            fn zero() -> Self {
+                $affine(<$ark_affine>::zero())
+            }
+
+            fn one() -> Self {
+                $affine(<$ark_affine>::generator())
+            }
+
+            fn is_zero(&self) -> bool {
+                self.0.is_zero()
+            }
+
+            fn negate(&mut self) {
+                self.0 = -self.0;
+            }
+
+            fn mul<S: Into<FrRepr>>(&self, other: S) -> $projective {
+                $projective(self.0.mul_bigint(BigInt(other.into().0)))
+            }
+
+            fn into_projective(&self) -> $projective {
+                $projective(self.0.into_group())
+            }
+        }
+
+        impl fmt::Debug for $affine {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl fmt::Display for $affine {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.0.xy() {
+                    Some((x, y)) => write!(
+                        f,
+                        "{}(x={}, y={})",
+                        stringify!($affine),
+                        $base::from(*x),
+                        $base::from(*y)
+                    ),
+                    None => write!(f, "{}(Infinity)", stringify!($affine)),
+                }
+            }
+        }
+
+        impl fmt::Debug for $projective {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl fmt::Display for $projective {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.into_affine())
+            }
+        }
+
+        impl Serialize for $projective {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                self.into_affine().serialize(s)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $projective {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                Ok($affine::deserialize(d)?.into_projective())
+            }
+        }
+
+        impl Serialize for $affine {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                serialize_affine(self, s)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $affine {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                deserialize_affine(d)
+            }
+        }
+    };
+}
+
+curve!(
+    G1,
+    G1Affine,
+    G1Compressed,
+    G1Uncompressed,
+    G1Prepared,
+    Fq,
+    ark_bn254::G1Projective,
+    ark_bn254::G1Affine,
+    ark_ec::bn::G1Prepared<ark_bn254::Config>,
+    32
+);
+
+curve!(
+    G2,
+    G2Affine,
+    G2Compressed,
+    G2Uncompressed,
+    G2Prepared,
+    Fq2,
+    ark_bn254::G2Projective,
+    ark_bn254::G2Affine,
+    ark_ec::bn::G2Prepared<ark_bn254::Config>,
+    64
+);
+
+impl PairingCurveAffine for G1Affine {
+    type Prepared = G1Prepared;
+    type Pair = G2Affine;
+    type PairingResult = Fq12;
+
+    fn prepare(&self) -> G1Prepared {
+        G1Prepared(self.0.into())
+    }
+
+    fn pairing_with(&self, other: &G2Affine) -> Fq12 {
+        Fq12(ark_bn254::Bn254::pairing(self.0, other.0).0)
+    }
+}
+
+impl PairingCurveAffine for G2Affine {
+    type Prepared = G2Prepared;
+    type Pair = G1Affine;
+    type PairingResult = Fq12;
+
+    fn prepare(&self) -> G2Prepared {
+        G2Prepared(self.0.into())
+    }
+
+    fn pairing_with(&self, other: &G1Affine) -> Fq12 {
+        Fq12(ark_bn254::Bn254::pairing(other.0, self.0).0)
+    }
+}
+
+/// Serializes a group element using its compressed representation.
+fn serialize_affine<S: Serializer, C: CurveAffine>(c: &C, s: S) -> Result<S::Ok, S::Error> {
+    let len = C::Compressed::size();
+    let mut tup = s.serialize_tuple(len)?;
+    for byte in c.into_compressed().as_ref() {
+        tup.serialize_element(byte)?;
+    }
+    tup.end()
+}
+
+/// Deserializes the compressed representation of a group element.
+fn deserialize_affine<'de, D: Deserializer<'de>, C: CurveAffine>(d: D) -> Result<C, D::Error> {
+    struct TupleVisitor<C> {
+        _ph: PhantomData<C>,
+    }
+
+    impl<'de, C: CurveAffine> Visitor<'de> for TupleVisitor<C> {
+        type Value = C;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a tuple of size {}", C::Compressed::size())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<C, A::Error> {
+            let mut compressed = C::Compressed::empty();
+            for (i, byte) in compressed.as_mut().iter_mut().enumerate() {
+                let len_err = || DeserializeError::invalid_length(i, &self);
+                *byte = seq.next_element()?.ok_or_else(len_err)?;
+            }
+            compressed.into_affine().map_err(|_| {
+                DeserializeError::custom("deserialized bytes don't encode a group element")
+            })
+        }
+    }
+
+    d.deserialize_tuple(C::Compressed::size(), TupleVisitor { _ph: PhantomData })
+}
@@ -0,0 +1,377 @@
+use ark_ff::{BigInt, BigInteger, FftField, Field as _, PrimeField as _};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use digest::generic_array::typenum::U48;
+use fff::{Field, LegendreSymbol, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, SqrtField};
+use paired::{BaseFromRO, Compress};
+use rand_core::RngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{cmp, fmt, io};
+
+/// Samples a uniform element by reducing 64 random bytes, which leaves a negligible bias.
+fn random_prime<F: ark_ff::PrimeField, R: RngCore>(rng: &mut R) -> F {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+fn legendre(symbol: ark_ff::LegendreSymbol) -> LegendreSymbol {
+    match symbol {
+        ark_ff::LegendreSymbol::Zero => LegendreSymbol::Zero,
+        ark_ff::LegendreSymbol::QuadraticResidue => LegendreSymbol::QuadraticResidue,
+        ark_ff::LegendreSymbol::QuadraticNonResidue => LegendreSymbol::QuadraticNonResidue,
+    }
+}
+
+macro_rules! repr {
+    ($name:ident) => {
+        /// Little-endian 64-bit limbs of an integer below 2^256.
+        #[derive(Copy, Clone, PartialEq, Eq, Default, Hash, Serialize, Deserialize)]
+        pub struct $name(pub [u64; 4]);
+
+        impl $name {
+            fn big(&self) -> BigInt<4> {
+                BigInt(self.0)
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> cmp::Ordering {
+                self.big().cmp(&other.big())
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "0x")?;
+                for limb in self.0.iter().rev() {
+                    write!(f, "{:016x}", limb)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl AsRef<[u64]> for $name {
+            fn as_ref(&self) -> &[u64] {
+                &self.0
+            }
+        }
+
+        impl AsMut<[u64]> for $name {
+            fn as_mut(&mut self) -> &mut [u64] {
+                &mut self.0
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(val: u64) -> Self {
+                $name([val, 0, 0, 0])
+            }
+        }
+
+        impl PrimeFieldRepr for $name {
+            fn sub_noborrow(&mut self, other: &Self) {
+                let mut big = self.big();
+                big.sub_with_borrow(&other.big());
+                self.0 = big.0;
+            }
+
+            fn add_nocarry(&mut self, other: &Self) {
+                let mut big = self.big();
+                big.add_with_carry(&other.big());
+                self.0 = big.0;
+            }
+
+            fn num_bits(&self) -> u32 {
+                self.big().num_bits()
+            }
+
+            fn is_zero(&self) -> bool {
+                self.big().is_zero()
+            }
+
+            fn is_odd(&self) -> bool {
+                self.big().is_odd()
+            }
+
+            fn is_even(&self) -> bool {
+                self.big().is_even()
+            }
+
+            fn div2(&mut self) {
+                let mut big = self.big();
+                big.div2();
+                self.0 = big.0;
+            }
+
+            fn shr(&mut self, amt: u32) {
+                let mut big = self.big();
+                big.divn(amt);
+                self.0 = big.0;
+            }
+
+            fn mul2(&mut self) {
+                let mut big = self.big();
+                big.mul2();
+                self.0 = big.0;
+            }
+
+            fn shl(&mut self, amt: u32) {
+                let mut big = self.big();
+                big.muln(amt);
+                self.0 = big.0;
+            }
+        }
+    };
+}
+
+repr!(FrRepr);
+repr!(FqRepr);
+
+macro_rules! field {
+    ($name:ident, $ark:ty, $random:expr) => {
+        #[derive(Copy, Clone, PartialEq, Eq, Default, Hash)]
+        pub struct $name(pub(crate) $ark);
+
+        impl From<$ark> for $name {
+            fn from(field: $ark) -> Self {
+                $name(field)
+            }
+        }
+
+        impl From<$name> for $ark {
+            fn from(field: $name) -> Self {
+                field.0
+            }
+        }
+
+        impl Field for $name {
+            fn random<R: RngCore>(rng: &mut R) -> Self {
+                $random(rng)
+            }
+
+            fn zero() -> Self {
+                $name(<$ark>::ZERO)
+            }
+
+            fn one() -> Self {
+                $name(<$ark>::ONE)
+            }
+
+            fn is_zero(&self) -> bool {
+                self.0 == <$ark>::ZERO
+            }
+
+            fn square(&mut self) {
+                self.0.square_in_place();
+            }
+
+            fn double(&mut self) {
+                self.0.double_in_place();
+            }
+
+            fn negate(&mut self) {
+                self.0.neg_in_place();
+            }
+
+            fn add_assign(&mut self, other: &Self) {
+                self.0 += other.0;
+            }
+
+            fn sub_assign(&mut self, other: &Self) {
+                self.0 -= other.0;
+            }
+
+            fn mul_assign(&mut self, other: &Self) {
+                self.0 *= other.0;
+            }
+
+            fn inverse(&self) -> Option<Self> {
+                self.0.inverse().map($name)
+            }
+
+            fn frobenius_map(&mut self, power: usize) {
+                self.0.frobenius_map_in_place(power);
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+    };
+}
+
+macro_rules! prime_field {
+    ($name:ident, $repr:ident, $ark:ty) => {
+        field!($name, $ark, random_prime_field::<$ark, _, $name>);
+
+        impl SqrtField for $name {
+            fn legendre(&self) -> LegendreSymbol {
+                legendre(self.0.legendre())
+            }
+
+            fn sqrt(&self) -> Option<Self> {
+                self.0.sqrt().map($name)
+            }
+        }
+
+        impl PrimeField for $name {
+            type Repr = $repr;
+
+            const NUM_BITS: u32 = <$ark>::MODULUS_BIT_SIZE;
+            const CAPACITY: u32 = <$ark>::MODULUS_BIT_SIZE - 1;
+            const S: u32 = <$ark>::TWO_ADICITY;
+
+            fn from_repr(repr: $repr) -> Result<Self, PrimeFieldDecodingError> {
+                <$ark>::from_bigint(repr.big())
+                    .map($name)
+                    .ok_or_else(|| PrimeFieldDecodingError::NotInField(repr.to_string()))
+            }
+
+            fn into_repr(&self) -> $repr {
+                $repr(self.0.into_bigint().0)
+            }
+
+            fn char() -> $repr {
+                $repr(<$ark>::MODULUS.0)
+            }
+
+            fn multiplicative_generator() -> Self {
+                $name(<$ark>::GENERATOR)
+            }
+
+            fn root_of_unity() -> Self {
+                $name(<$ark>::TWO_ADIC_ROOT_OF_UNITY)
+            }
+
+            fn from_random_bytes(bytes: &[u8]) -> Option<Self> {
+                <$ark>::from_random_bytes(bytes).map($name)
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(field: $name) -> $repr {
+                field.into_repr()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.into_repr())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                self.into_repr().serialize(s)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                use serde::de::Error;
+                $name::from_repr($repr::deserialize(d)?).map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+fn random_prime_field<A: ark_ff::PrimeField, R: RngCore, F: From<A>>(rng: &mut R) -> F {
+    F::from(random_prime::<A, R>(rng))
+}
+
+/// Extension fields are serialized as the list of their coefficients over `Fq`.
+macro_rules! extension_field {
+    ($name:ident, $ark:ty) => {
+        field!($name, $ark, |rng: &mut R| {
+            let elems = (0..<$ark>::extension_degree())
+                .map(|_| random_prime::<ark_bn254::Fq, R>(rng))
+                .collect::<Vec<_>>();
+            $name(<$ark>::from_base_prime_field_elems(&elems).unwrap())
+        });
+
+        impl $name {
+            fn coefficients(&self) -> Vec<Fq> {
+                self.0.to_base_prime_field_elements().map(Fq).collect()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({:?})", stringify!($name), self.coefficients())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                self.coefficients().serialize(s)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                use serde::de::Error;
+                let elems = Vec::<Fq>::deserialize(d)?
+                    .into_iter()
+                    .map(|fq| fq.0)
+                    .collect::<Vec<_>>();
+                <$ark>::from_base_prime_field_elems(&elems)
+                    .map($name)
+                    .ok_or_else(|| D::Error::invalid_length(elems.len(), &"the extension degree"))
+            }
+        }
+    };
+}
+
+prime_field!(Fr, FrRepr, ark_bn254::Fr);
+prime_field!(Fq, FqRepr, ark_bn254::Fq);
+extension_field!(Fq2, ark_bn254::Fq2);
+extension_field!(Fq12, ark_bn254::Fq12);
+
+impl SqrtField for Fq2 {
+    fn legendre(&self) -> LegendreSymbol {
+        legendre(self.0.legendre())
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        self.0.sqrt().map(Fq2)
+    }
+}
+
+/// Reduces 48 big-endian bytes modulo r, as `hash_to_field` of RFC 9380 does.
+#[allow(deprecated)]
+impl BaseFromRO for Fr {
+    type BaseLength = U48;
+
+    fn from_okm(okm: &digest::generic_array::GenericArray<u8, U48>) -> Fr {
+        Fr(ark_bn254::Fr::from_be_bytes_mod_order(okm))
+    }
+}
+
+/// `Fq12` has no torus compression here, so all twelve coefficients are written.
+impl Compress for Fq12 {
+    fn write_compressed<W: io::Write>(self, out: W) -> io::Result<()> {
+        self.0
+            .serialize_compressed(out)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    fn read_compressed<R: io::Read>(source: R) -> io::Result<Self> {
+        ark_bn254::Fq12::deserialize_compressed(source)
+            .map(Fq12)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
@@ -0,0 +1,181 @@
+//! Hashing to G1 and G2 with the Shallue-van de Woestijne map of RFC 9380, Section 6.6.1,
+//! as in the `BN254G1_XMD:SHA-256_SVDW_RO_` and `BN254G2_XMD:SHA-256_SVDW_RO_` suites.
+use super::curves::{G1, G2};
+use ark_bn254::{Fq, Fq2};
+use ark_ec::bn::BnConfig;
+use ark_ec::short_weierstrass::{Affine, Projective, SWCurveConfig};
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ff::{BigInteger, Field, PrimeField, Zero};
+use digest::generic_array::typenum::U48;
+use paired::{hash_to_field, BaseFromRO, ExpandMsgXmd};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+/// The domain separation tag used by `CurveProjective::hash`.
+pub(crate) const DST: &[u8] = b"rust-searchable-pke-V01-BN254_XMD:SHA-256_SVDW_RO_";
+
+/// Bytes per base field element in `hash_to_field`, i.e. ceil((254 + 128) / 8).
+const L: usize = 48;
+
+/// `L` bytes of the `expand_message_xmd` output, one per base field coefficient.
+struct Chunk(Vec<u8>);
+
+#[allow(deprecated)]
+impl BaseFromRO for Chunk {
+    type BaseLength = U48;
+
+    fn from_okm(okm: &digest::generic_array::GenericArray<u8, U48>) -> Self {
+        Chunk(okm.to_vec())
+    }
+}
+
+trait HashField: Field {
+    /// Builds an element from `L * extension_degree` uniform big-endian bytes.
+    fn from_uniform_bytes(bytes: &[u8]) -> Self;
+
+    /// The sign of the element as defined in RFC 9380, Section 4.1.
+    fn sgn0(&self) -> bool;
+}
+
+impl HashField for Fq {
+    fn from_uniform_bytes(bytes: &[u8]) -> Self {
+        Fq::from_be_bytes_mod_order(bytes)
+    }
+
+    fn sgn0(&self) -> bool {
+        self.into_bigint().is_odd()
+    }
+}
+
+impl HashField for Fq2 {
+    fn from_uniform_bytes(bytes: &[u8]) -> Self {
+        Fq2::new(
+            Fq::from_uniform_bytes(&bytes[..L]),
+            Fq::from_uniform_bytes(&bytes[L..]),
+        )
+    }
+
+    fn sgn0(&self) -> bool {
+        self.c0.sgn0() || (self.c0.is_zero() && self.c1.sgn0())
+    }
+}
+
+/// The constants c1..c4 of the map for `Z = 1`, which both suites use.
+struct SvdwConstants<F> {
+    c1: F,
+    c2: F,
+    c3: F,
+    c4: F,
+}
+
+impl<F: HashField> SvdwConstants<F> {
+    fn new<P: SWCurveConfig<BaseField = F>>() -> Self {
+        let z = F::one();
+        let three_z2_4a = z.square() * F::from(3u64) + P::COEFF_A * F::from(4u64);
+        // c1 = g(Z)
+        let c1 = (z.square() + P::COEFF_A) * z + P::COEFF_B;
+        // c2 = -Z / 2
+        let c2 = -z * F::from(2u64).inverse().unwrap();
+        // c3 = sqrt(-g(Z) * (3 * Z^2 + 4 * A)) with sgn0(c3) == 0
+        let mut c3 = (-c1 * three_z2_4a).sqrt().unwrap();
+        if c3.sgn0() {
+            c3 = -c3;
+        }
+        // c4 = -4 * g(Z) / (3 * Z^2 + 4 * A)
+        let c4 = -c1 * F::from(4u64) * three_z2_4a.inverse().unwrap();
+        Self { c1, c2, c3, c4 }
+    }
+
+    fn map_to_curve<P: SWCurveConfig<BaseField = F>>(&self, u: F) -> Affine<P> {
+        let g = |x: F| (x.square() + P::COEFF_A) * x + P::COEFF_B;
+        let is_square = |x: F| x.legendre().is_qr() || x.is_zero();
+
+        let tv1 = u.square() * self.c1;
+        let tv2 = F::one() + tv1;
+        let tv1 = F::one() - tv1;
+        let tv3 = (tv1 * tv2).inverse().unwrap_or_else(F::zero);
+        let tv4 = u * tv1 * tv3 * self.c3;
+        let x1 = self.c2 - tv4;
+        let x2 = self.c2 + tv4;
+        let x3 = (tv2.square() * tv3).square() * self.c4 + F::one();
+        let x = if is_square(g(x1)) {
+            x1
+        } else if is_square(g(x2)) {
+            x2
+        } else {
+            x3
+        };
+        let mut y = g(x).sqrt().unwrap();
+        if u.sgn0() != y.sgn0() {
+            y = -y;
+        }
+        Affine::new_unchecked(x, y)
+    }
+}
+
+/// Hashes `msg` to two field elements and adds their images under the map.
+fn hash_to_curve<P: SWCurveConfig>(
+    constants: &SvdwConstants<P::BaseField>,
+    msg: &[u8],
+    dst: &[u8],
+) -> Projective<P>
+where
+    P::BaseField: HashField,
+{
+    let degree = P::BaseField::extension_degree() as usize;
+    let bytes = hash_to_field::<Chunk, ExpandMsgXmd<Sha256>>(msg, dst, 2 * degree)
+        .into_iter()
+        .flat_map(|chunk| chunk.0)
+        .collect::<Vec<u8>>();
+    let u0 = P::BaseField::from_uniform_bytes(&bytes[..L * degree]);
+    let u1 = P::BaseField::from_uniform_bytes(&bytes[L * degree..]);
+    constants.map_to_curve::<P>(u0).into_group() + constants.map_to_curve::<P>(u1)
+}
+
+impl G1 {
+    /// Hashes to G1 following `BN254G1_XMD:SHA-256_SVDW_RO_` with the given tag.
+    /// G1 has a trivial cofactor, so the sum of the two mapped points is returned as is.
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        static CONSTANTS: OnceLock<SvdwConstants<Fq>> = OnceLock::new();
+        let constants = CONSTANTS.get_or_init(SvdwConstants::new::<ark_bn254::g1::Config>);
+        G1(hash_to_curve(constants, msg, dst))
+    }
+}
+
+impl G2 {
+    /// Hashes to G2 following `BN254G2_XMD:SHA-256_SVDW_RO_` with the given tag.
+    pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Self {
+        static CONSTANTS: OnceLock<SvdwConstants<Fq2>> = OnceLock::new();
+        let constants = CONSTANTS.get_or_init(SvdwConstants::new::<ark_bn254::g2::Config>);
+        G2(clear_cofactor_g2(hash_to_curve(constants, msg, dst)))
+    }
+}
+
+/// Clears the G2 cofactor with the endomorphism psi and the BN parameter x as [x]P + psi([3x]P) + psi^2([x]P) + psi^3(P)
+/// (Fuentes-Castaneda et al.), which is a multiple of the cofactor and matches other BN254 libraries.
+fn clear_cofactor_g2(point: ark_bn254::G2Projective) -> ark_bn254::G2Projective {
+    let x_point = point.mul_bigint(ark_bn254::Config::X);
+    let u1 = psi(x_point.double() + x_point);
+    let u2 = psi(psi(x_point));
+    let u3 = psi(psi(psi(point)));
+    x_point + u1 + u2 + u3
+}
+
+/// The untwist-Frobenius-twist endomorphism psi(x, y) = (u * conj(x), v * conj(y)).
+fn psi(point: ark_bn254::G2Projective) -> ark_bn254::G2Projective {
+    let affine = point.into_affine();
+    match affine.xy() {
+        Some((x, y)) => {
+            let mut x = *x;
+            let mut y = *y;
+            x.conjugate_in_place();
+            y.conjugate_in_place();
+            Affine::new_unchecked(
+                x * ark_bn254::Config::TWIST_MUL_BY_Q_X,
+                y * ark_bn254::Config::TWIST_MUL_BY_Q_Y,
+            )
+            .into_group()
+        }
+        None => point,
+    }
+}
@@ -0,0 +1,272 @@
+//! The BN254 (alt_bn128) pairing curve, backed by arkworks and exposed through the
+//! `paired`/`groupy`/`fff` traits so that every scheme in this crate can run on it.
+//! It is the curve of the Ethereum pairing precompiles and of most SNARK toolchains;
+//! each wrapper type converts from and into its `ark_bn254` counterpart.
+//!
+//! Keywords are hashed to G1 with the SvdW map of RFC 9380, so trapdoors and ciphertexts
+//! are not interchangeable with those made on BLS12-381, and the binary encodings record the curve.
+mod curves;
+mod fields;
+mod hash;
+
+pub use curves::*;
+pub use fields::*;
+
+use crate::{BaseROFr, EngineId};
+use ark_ec::pairing::{MillerLoopOutput, Pairing};
+use fff::ScalarEngine;
+use paired::Engine;
+
+#[derive(Clone, Debug)]
+pub struct Bn254;
+
+impl ScalarEngine for Bn254 {
+    type Fr = Fr;
+}
+
+impl Engine for Bn254 {
+    type G1 = G1;
+    type G1Affine = G1Affine;
+    type G2 = G2;
+    type G2Affine = G2Affine;
+    type Fq = Fq;
+    type Fqe = Fq2;
+    type Fqk = Fq12;
+
+    fn miller_loop<'a, I>(i: I) -> Fq12
+    where
+        I: IntoIterator<Item = &'a (&'a G1Prepared, &'a G2Prepared)>,
+    {
+        let (g1s, g2s): (Vec<_>, Vec<_>) = i
+            .into_iter()
+            .map(|(g1, g2)| (g1.0.clone(), g2.0.clone()))
+            .unzip();
+        Fq12(ark_bn254::Bn254::multi_miller_loop(g1s, g2s).0)
+    }
+
+    fn final_exponentiation(f: &Fq12) -> Option<Fq12> {
+        ark_bn254::Bn254::final_exponentiation(MillerLoopOutput(f.0)).map(|out| Fq12(out.0))
+    }
+}
+
+impl EngineId for Bn254 {
+    const CURVE_ID: u8 = 2;
+}
+
+impl BaseROFr<Bn254> for Fr {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pecdk::{self, SearchSym};
+    use crate::{peks, WireError};
+    use fff::{Field, PrimeField};
+    use groupy::{CurveAffine, CurveProjective, EncodedPoint, GroupDecodingError};
+    use paired::{bls12_381::Bls12, Compress, PairingCurveAffine};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn fq(hex: &str) -> ark_bn254::Fq {
+        use ark_ff::PrimeField;
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect::<Vec<u8>>();
+        ark_bn254::Fq::from_be_bytes_mod_order(&bytes)
+    }
+
+    // Test vectors from gnark-crypto's ecc/bn254/hash_vectors_test.go.
+    const LONG_Q: &str = "q128_qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq";
+
+    #[test]
+    fn test_bn254_hash_to_g1_vectors() {
+        let dst = b"QUUX-V01-CS02-with-BN254G1_XMD:SHA-256_SVDW_RO_";
+        let vectors = [
+            (
+                "",
+                "0a976ab906170db1f9638d376514dbf8c42aef256a54bbd48521f20749e59e86",
+                "02925ead66b9e68bfc309b014398640ab55f6619ab59bc1fab2210ad4c4d53d5",
+            ),
+            (
+                "abc",
+                "23f717bee89b1003957139f193e6be7da1df5f1374b26a4643b0378b5baf53d1",
+                "04142f826b71ee574452dbc47e05bc3e1a647478403a7ba38b7b93948f4e151d",
+            ),
+            (
+                "abcdef0123456789",
+                "187dbf1c3c89aceceef254d6548d7163fdfa43084145f92c4c91c85c21442d4a",
+                "0abd99d5b0000910b56058f9cc3b0ab0a22d47cf27615f588924fac1e5c63b4d",
+            ),
+            (
+                LONG_Q,
+                "00fe2b0743575324fc452d590d217390ad48e5a16cf051bee5c40a2eba233f5c",
+                "0794211e0cc72d3cbbdf8e4e5cd6e7d7e78d101ff94862caae8acbe63e9fdc78",
+            ),
+        ];
+        for (msg, x, y) in vectors {
+            let expected = ark_bn254::G1Affine::new(fq(x), fq(y));
+            let hashed = G1::hash_to_curve(msg.as_bytes(), dst).into_affine();
+            assert_eq!(hashed, G1Affine::from(expected), "msg {:?}", msg);
+        }
+    }
+
+    #[test]
+    fn test_bn254_hash_to_g2_vectors() {
+        let dst = b"QUUX-V01-CS02-with-BN254G2_XMD:SHA-256_SVDW_RO_";
+        let vectors = [
+            (
+                "",
+                [
+                    "1192005a0f121921a6d5629946199e4b27ff8ee4d6dd4f9581dc550ade851300",
+                    "1747d950a6f23c16156e2171bce95d1189b04148ad12628869ed21c96a8c9335",
+                    "0498f6bb5ac309a07d9a8b88e6ff4b8de0d5f27a075830e1eb0e68ea318201d8",
+                    "2c9755350ca363ef2cf541005437221c5740086c2e909b71d075152484e845f4",
+                ],
+            ),
+            (
+                "abc",
+                [
+                    "16c88b54eec9af86a41569608cd0f60aab43464e52ce7e6e298bf584b94fccd2",
+                    "0b5db3ca7e8ef5edf3a33dfc3242357fbccead98099c3eb564b3d9d13cba4efd",
+                    "1c42ba524cb74db8e2c680449746c028f7bea923f245e69f89256af2d6c5f3ac",
+                    "22d02d2da7f288545ff8789e789902245ab08c6b1d253561eec789ec2c1bd630",
+                ],
+            ),
+            (
+                LONG_Q,
+                [
+                    "2cffc213fb63d00d923cb22cda5a2904837bb93a2fe6e875c532c51744388341",
+                    "2718ef38d1bc4347f0266c774c8ef4ee5fa7056cc27a4bd7ecf7a888efb95b26",
+                    "232553f728341afa64ce66d00535764557a052e38657594e10074ad28728c584",
+                    "2206ec0a9288f31ed78531c37295df3b56c42a1284443ee9893adb1521779001",
+                ],
+            ),
+        ];
+        for (msg, [x0, x1, y0, y1]) in vectors {
+            let expected = ark_bn254::G2Affine::new(
+                ark_bn254::Fq2::new(fq(x0), fq(x1)),
+                ark_bn254::Fq2::new(fq(y0), fq(y1)),
+            );
+            let hashed = G2::hash_to_curve(msg.as_bytes(), dst).into_affine();
+            assert_eq!(hashed, G2Affine::from(expected), "msg {:?}", msg);
+        }
+    }
+
+    #[test]
+    fn test_bn254_engine() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let p = G1::random(&mut rng).into_affine();
+        let q = G2::random(&mut rng).into_affine();
+        let mut ab = a;
+        ab.mul_assign(&b);
+        let lhs = Bn254::pairing(p.mul(a), q.mul(b));
+        let rhs = Bn254::pairing(p, q).pow(ab.into_repr());
+        assert_eq!(lhs, rhs);
+        assert_eq!(p.pairing_with(&q), q.pairing_with(&p));
+
+        let mut neg_p = p;
+        neg_p.negate();
+        let miller = Bn254::miller_loop(
+            [
+                (&p.prepare(), &q.prepare()),
+                (&neg_p.prepare(), &q.prepare()),
+            ]
+            .iter(),
+        );
+        assert_eq!(Bn254::final_exponentiation(&miller).unwrap(), Fq12::one());
+
+        let mut bytes = Vec::new();
+        lhs.write_compressed(&mut bytes).unwrap();
+        assert_eq!(Fq12::read_compressed(&bytes[..]).unwrap(), lhs);
+
+        assert_eq!(Fr::from_repr(a.into_repr()).unwrap(), a);
+        assert!(Fr::from_repr(Fr::char()).is_err());
+        let json = serde_json::to_string(&(a, p, q, lhs)).unwrap();
+        let decoded: (Fr, G1Affine, G2Affine, Fq12) = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, (a, p, q, lhs));
+    }
+
+    #[test]
+    fn test_bn254_point_encoding() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let p = G1::random(&mut rng).into_affine();
+        let q = G2::random(&mut rng).into_affine();
+        assert_eq!(p.into_compressed().into_affine().unwrap(), p);
+        assert_eq!(p.into_uncompressed().into_affine().unwrap(), p);
+        assert_eq!(q.into_compressed().into_affine().unwrap(), q);
+        assert_eq!(q.into_uncompressed().into_affine().unwrap(), q);
+        let zero = G2Affine::zero();
+        assert_eq!(zero.into_compressed().into_affine().unwrap(), zero);
+
+        // (1, 1) is not on y^2 = x^3 + 3.
+        let mut off_curve = G1Uncompressed::empty();
+        off_curve.as_mut()[0] = 1;
+        off_curve.as_mut()[32] = 1;
+        assert!(matches!(
+            off_curve.into_affine(),
+            Err(GroupDecodingError::NotOnCurve)
+        ));
+
+        // A point of the twist outside the order-r subgroup.
+        let x = ark_bn254::Fq2::new(ark_bn254::Fq::from(1u64), ark_bn254::Fq::from(0u64));
+        let outside = (0u64..)
+            .find_map(|i| {
+                ark_bn254::G2Affine::get_point_from_x_unchecked(x + ark_bn254::Fq2::from(i), false)
+            })
+            .unwrap();
+        assert!(!outside.is_in_correct_subgroup_assuming_on_curve());
+        let mut encoded = G2Compressed::empty();
+        encoded
+            .as_mut()
+            .copy_from_slice(G2Compressed::from_affine(G2Affine::from(outside)).as_ref());
+        assert!(matches!(
+            encoded.into_affine(),
+            Err(GroupDecodingError::NotInSubgroup)
+        ));
+        assert!(encoded.into_affine_unchecked().is_ok());
+    }
+
+    #[test]
+    fn test_bn254_pecdk_and_peks() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let keywords = vec![b"alpha".to_vec(), b"beta".to_vec(), b"gamma".to_vec()];
+        let secret_key = pecdk::SecretKey::<Bn254>::gen(&mut rng, 4);
+        let public_key = secret_key.into_public_key();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords.clone(), &mut rng)
+            .unwrap();
+        let and_td = secret_key
+            .gen_trapdoor::<_, Fr>(keywords[0..2].to_vec(), SearchSym::AND, &mut rng)
+            .unwrap();
+        let or_td = secret_key
+            .gen_trapdoor::<_, Fr>(vec![b"delta".to_vec()], SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(and_td.test(&ct).unwrap());
+        assert!(!or_td.test(&ct).unwrap());
+
+        let ct_bytes = ct.to_bytes().unwrap();
+        let decoded = pecdk::Ciphertext::<Bn254>::from_bytes(&ct_bytes).unwrap();
+        assert!(and_td.test(&decoded).unwrap());
+        assert!(matches!(
+            pecdk::Ciphertext::<Bls12>::from_bytes(&ct_bytes),
+            Err(WireError::CurveMismatch(2, 1))
+        ));
+
+        let peks_sk = peks::SecretKey::<Bn254>::gen(&mut rng);
+        let peks_pk = peks_sk.into_public_key(&mut rng);
+        let peks_ct = peks_pk.encrypt(b"alpha", &mut rng).unwrap();
+        assert!(peks_sk.gen_trapdoor(b"alpha").test(&peks_ct).unwrap());
+        assert!(!peks_sk.gen_trapdoor(b"beta").test(&peks_ct).unwrap());
+    }
+}
@@ -1,17 +1,653 @@
+use crate::curve::CurveEngine;
+use crate::wipe::wipe;
+use crate::EngineId;
+use errno::{set_errno, Errno};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
 use std::ffi::*;
+use std::fmt::Display;
+use std::io::{self, Write};
 use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
 
-pub(crate) fn str2ptr(str: String) -> *mut c_char {
-    let c_str = CString::new(str).unwrap();
-    c_str.into_raw()
+pub(crate) const EINVAL: i32 = 22;
+
+/// Result of a C API call.
+/// When it is not `Success`, `lastErrorMessage()` describes the failure
+/// and the output argument is left untouched.
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CErrorCode {
+    Success = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidArgument = 3,
+    InvalidObject = 4,
+    SerializeFailure = 5,
+    CryptoFailure = 6,
+    Panic = 7,
+    CurveMismatch = 8,
+}
+
+#[derive(Debug)]
+pub(crate) struct CError {
+    code: CErrorCode,
+    message: String,
+}
+
+impl CError {
+    pub(crate) fn new<M: Display>(code: CErrorCode, message: M) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn invalid_object<M: Display>(message: M) -> Self {
+        Self::new(CErrorCode::InvalidObject, message)
+    }
+
+    pub(crate) fn crypto<M: Display>(message: M) -> Self {
+        Self::new(CErrorCode::CryptoFailure, message)
+    }
+}
+
+thread_local! {
+    static LAST_ERROR_MESSAGE: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Returns the message of the last failed call on the current thread, or NULL.
+/// The string is owned by the library and stays valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn lastErrorMessage() -> *const c_char {
+    LAST_ERROR_MESSAGE.with(|message| {
+        message
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+fn set_last_error(name: &str, message: &str) {
+    let message = format!("{}: {}", name, message).replace('\0', "");
+    LAST_ERROR_MESSAGE.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with an unknown payload".to_string()
+    }
+}
+
+/// Runs `f` so that neither an error nor a panic escapes through the FFI boundary.
+pub(crate) fn ffi_call<F>(name: &str, f: F) -> CErrorCode
+where
+    F: FnOnce() -> Result<(), CError>,
+{
+    LAST_ERROR_MESSAGE.with(|last| *last.borrow_mut() = None);
+    let (code, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return CErrorCode::Success,
+        Ok(Err(err)) => (err.code, err.message),
+        Err(payload) => (CErrorCode::Panic, panic_message(payload)),
+    };
+    set_last_error(name, &message);
+    set_errno(Errno(EINVAL));
+    code
 }
 
-pub(crate) fn ptr2str<'a>(ptr: *mut c_char) -> &'a str {
+/// Same as `ffi_call`, but writes the value computed by `f` to `out` on success.
+pub(crate) fn ffi_output<T, F>(name: &str, out: *mut T, f: F) -> CErrorCode
+where
+    F: FnOnce() -> Result<T, CError>,
+{
+    ffi_call(name, || {
+        if out.is_null() {
+            return Err(CError::new(
+                CErrorCode::NullPointer,
+                "the output pointer is NULL",
+            ));
+        }
+        let value = f()?;
+        unsafe { out.write(value) };
+        Ok(())
+    })
+}
+
+pub(crate) fn str2ptr(str: String) -> Result<*mut c_char, CError> {
+    let c_str = CString::new(str).map_err(|err| CError::new(CErrorCode::SerializeFailure, err))?;
+    Ok(c_str.into_raw())
+}
+
+pub(crate) fn ptr2str<'a>(ptr: *const c_char) -> Result<&'a str, CError> {
+    if ptr.is_null() {
+        return Err(CError::new(
+            CErrorCode::NullPointer,
+            "a string pointer is NULL",
+        ));
+    }
     let cstr = unsafe { CStr::from_ptr(ptr) };
-    cstr.to_str().unwrap()
+    cstr.to_str()
+        .map_err(|err| CError::new(CErrorCode::InvalidUtf8, err))
+}
+
+pub(crate) fn ptrs2strs<'a>(ptrs: *const *mut c_char, len: usize) -> Result<Vec<&'a str>, CError> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if ptrs.is_null() {
+        return Err(CError::new(
+            CErrorCode::NullPointer,
+            "a string array pointer is NULL",
+        ));
+    }
+    let slice = unsafe { std::slice::from_raw_parts(ptrs, len) };
+    slice.iter().map(|ptr| ptr2str(*ptr)).collect()
+}
+
+pub(crate) fn ptr2bytes<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], CError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(CError::new(
+            CErrorCode::NullPointer,
+            "a byte pointer is NULL",
+        ));
+    }
+    Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+pub(crate) fn ptrs2bytes_vec(
+    ptrs: *const *const u8,
+    lens: *const usize,
+    len: usize,
+) -> Result<Vec<Vec<u8>>, CError> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if ptrs.is_null() || lens.is_null() {
+        return Err(CError::new(
+            CErrorCode::NullPointer,
+            "a byte array pointer is NULL",
+        ));
+    }
+    let ptr_slice = unsafe { std::slice::from_raw_parts(ptrs, len) };
+    let len_slice = unsafe { std::slice::from_raw_parts(lens, len) };
+    ptr_slice
+        .iter()
+        .zip(len_slice)
+        .map(|(ptr, len)| Ok(ptr2bytes(*ptr, *len)?.to_vec()))
+        .collect()
+}
+
+/// Every C API object is a JSON object holding the id of its curve next to its value,
+/// so that an object made by a library built for another curve is rejected.
+#[derive(Serialize)]
+struct CurveTagged<'a, T> {
+    curve: u8,
+    value: &'a T,
+}
+
+#[derive(Deserialize)]
+struct CurveTag {
+    curve: u8,
+}
+
+#[derive(Deserialize)]
+struct CurveTaggedValue<T> {
+    value: T,
+}
+
+fn curve_tagged<T>(value: &T) -> CurveTagged<'_, T> {
+    CurveTagged {
+        curve: CurveEngine::CURVE_ID,
+        value,
+    }
+}
+
+pub(crate) fn from_json_ptr<'a, T: Deserialize<'a>>(ptr: *const c_char) -> Result<T, CError> {
+    let str = ptr2str(ptr)?;
+    let tag: CurveTag = serde_json::from_str(str).map_err(CError::invalid_object)?;
+    if tag.curve != CurveEngine::CURVE_ID {
+        return Err(CError::new(
+            CErrorCode::CurveMismatch,
+            format!(
+                "the object is for the curve {}, but the library uses the curve {}",
+                tag.curve,
+                CurveEngine::CURVE_ID
+            ),
+        ));
+    }
+    let tagged: CurveTaggedValue<T> = serde_json::from_str(str).map_err(CError::invalid_object)?;
+    Ok(tagged.value)
+}
+
+pub(crate) fn to_json_ptr<T: Serialize>(val: &T) -> Result<*mut c_char, CError> {
+    let str = serde_json::to_string(&curve_tagged(val))
+        .map_err(|err| CError::new(CErrorCode::SerializeFailure, err))?;
+    str2ptr(str)
+}
+
+/// Counts the bytes written to it, so that a buffer can be allocated at its final size.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Like `to_json_ptr`, but for secrets: the JSON is written once into a buffer sized for it
+/// and its NUL, so no reallocation leaves a freed copy behind, and the buffer is wiped on failure.
+pub(crate) fn to_secret_json_ptr<T: Serialize>(val: &T) -> Result<*mut c_char, CError> {
+    let val = curve_tagged(val);
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, &val)
+        .map_err(|err| CError::new(CErrorCode::SerializeFailure, err))?;
+    let mut bytes = Vec::with_capacity(counter.0 + 1);
+    if let Err(err) = serde_json::to_writer(&mut bytes, &val) {
+        wipe(&mut bytes, 0);
+        return Err(CError::new(CErrorCode::SerializeFailure, err));
+    }
+    bytes.push(0);
+    debug_assert_eq!(bytes.len(), bytes.capacity());
+    match CString::from_vec_with_nul(bytes) {
+        Ok(c_str) => Ok(c_str.into_raw()),
+        Err(err) => {
+            let mut bytes = err.into_bytes();
+            wipe(&mut bytes, 0);
+            Err(CError::new(
+                CErrorCode::SerializeFailure,
+                "the serialized secret contains a NUL byte",
+            ))
+        }
+    }
 }
 
 pub(crate) fn drop_ptr(ptr: *mut c_char) {
+    if ptr.is_null() {
+        return;
+    }
     let cstring = unsafe { CString::from_raw(ptr) };
     drop(cstring);
 }
+
+/// Like `drop_ptr`, but overwrites the string with zeros before freeing it.
+pub(crate) fn drop_secret_ptr(ptr: *mut c_char) {
+    if ptr.is_null() {
+        return;
+    }
+    let mut bytes = unsafe { CString::from_raw(ptr) }.into_bytes_with_nul();
+    wipe(&mut bytes, 0);
+    drop(bytes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pecdk::*;
+
+    #[test]
+    fn test_c_api_error_codes() {
+        let mut secret_key = CPecdkSecretKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(pecdkGenSecretKey(2, &mut secret_key), CErrorCode::Success);
+        assert!(lastErrorMessage().is_null());
+
+        assert_eq!(
+            pecdkGenPublicKey(secret_key.clone(), ptr::null_mut()),
+            CErrorCode::NullPointer
+        );
+        assert!(!lastErrorMessage().is_null());
+
+        let mut public_key = CPecdkPublicKey {
+            ptr: ptr::null_mut(),
+        };
+        let invalid_key = CPecdkSecretKey {
+            ptr: str2ptr("{}".to_string()).unwrap(),
+        };
+        assert_eq!(
+            pecdkGenPublicKey(invalid_key.clone(), &mut public_key),
+            CErrorCode::InvalidObject
+        );
+        assert!(public_key.ptr.is_null());
+        let message = ptr2str(lastErrorMessage()).unwrap();
+        assert!(message.starts_with("pecdkGenPublicKey: "));
+        pecdkFreeSecretKey(invalid_key);
+
+        let mut keywords = vec![
+            str2ptr("a".to_string()).unwrap(),
+            str2ptr("b".to_string()).unwrap(),
+        ];
+        assert_eq!(
+            pecdkGenPublicKey(secret_key.clone(), &mut public_key),
+            CErrorCode::Success
+        );
+        let mut ciphertext = CPecdkCiphertext {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkEncryptKeyword(
+                public_key.clone(),
+                keywords.as_mut_ptr(),
+                keywords.len(),
+                &mut ciphertext
+            ),
+            CErrorCode::Success
+        );
+        let mut trapdoor = CPecdkTrapdoor {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenTrapdoor(
+                secret_key.clone(),
+                keywords.as_mut_ptr(),
+                1,
+                0,
+                &mut trapdoor
+            ),
+            CErrorCode::Success
+        );
+        let mut result = false;
+        assert_eq!(
+            pecdkTest(ciphertext.clone(), trapdoor.clone(), &mut result),
+            CErrorCode::Success
+        );
+        assert!(result);
+
+        let mut threshold_trapdoor = CPecdkTrapdoor {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenThresholdTrapdoor(
+                secret_key.clone(),
+                keywords.as_mut_ptr(),
+                2,
+                3,
+                &mut threshold_trapdoor
+            ),
+            CErrorCode::InvalidArgument
+        );
+        assert_eq!(
+            pecdkGenThresholdTrapdoor(
+                secret_key.clone(),
+                keywords.as_mut_ptr(),
+                2,
+                2,
+                &mut threshold_trapdoor
+            ),
+            CErrorCode::Success
+        );
+        assert_eq!(
+            pecdkTest(ciphertext.clone(), threshold_trapdoor.clone(), &mut result),
+            CErrorCode::Success
+        );
+        assert!(result);
+        pecdkFreeTrapdoor(threshold_trapdoor);
+        for keyword in keywords {
+            drop_ptr(keyword);
+        }
+        pecdkFreeSecretKey(secret_key);
+        pecdkFreePublicKey(public_key);
+        pecdkFreeCiphertext(ciphertext);
+        pecdkFreeTrapdoor(trapdoor);
+    }
+
+    #[test]
+    fn test_c_api_binary_keywords() {
+        let mut secret_key = CPecdkSecretKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(pecdkGenSecretKey(2, &mut secret_key), CErrorCode::Success);
+        let mut public_key = CPecdkPublicKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenPublicKey(secret_key.clone(), &mut public_key),
+            CErrorCode::Success
+        );
+        let keywords: Vec<&[u8]> = vec![b"a\0b", &[0xff, 0xfe, 0x00]];
+        let ptrs = keywords.iter().map(|k| k.as_ptr()).collect::<Vec<_>>();
+        let lens = keywords.iter().map(|k| k.len()).collect::<Vec<_>>();
+        let mut ciphertext = CPecdkCiphertext {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkEncryptKeywordBytes(
+                public_key.clone(),
+                ptrs.as_ptr(),
+                lens.as_ptr(),
+                ptrs.len(),
+                &mut ciphertext
+            ),
+            CErrorCode::Success
+        );
+
+        let mut trapdoor = CPecdkTrapdoor {
+            ptr: ptr::null_mut(),
+        };
+        let mut result = false;
+        assert_eq!(
+            pecdkGenTrapdoorBytes(
+                secret_key.clone(),
+                ptrs[1..].as_ptr(),
+                lens[1..].as_ptr(),
+                1,
+                0,
+                &mut trapdoor
+            ),
+            CErrorCode::Success
+        );
+        assert_eq!(
+            pecdkTest(ciphertext.clone(), trapdoor.clone(), &mut result),
+            CErrorCode::Success
+        );
+        assert!(result);
+        pecdkFreeTrapdoor(trapdoor);
+
+        // "a" is a strict prefix of the first keyword and must not match it.
+        let truncated = [ptrs[0]];
+        let truncated_len = [1];
+        let mut trapdoor = CPecdkTrapdoor {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenTrapdoorBytes(
+                secret_key.clone(),
+                truncated.as_ptr(),
+                truncated_len.as_ptr(),
+                1,
+                0,
+                &mut trapdoor
+            ),
+            CErrorCode::Success
+        );
+        assert_eq!(
+            pecdkTest(ciphertext.clone(), trapdoor.clone(), &mut result),
+            CErrorCode::Success
+        );
+        assert!(!result);
+
+        assert_eq!(
+            pecdkEncryptKeywordBytes(
+                public_key.clone(),
+                ptrs.as_ptr(),
+                ptr::null(),
+                ptrs.len(),
+                &mut ciphertext
+            ),
+            CErrorCode::NullPointer
+        );
+        pecdkFreeSecretKey(secret_key);
+        pecdkFreePublicKey(public_key);
+        pecdkFreeCiphertext(ciphertext);
+        pecdkFreeTrapdoor(trapdoor);
+    }
+
+    #[test]
+    fn test_c_api_secret_json() {
+        let mut rng = rand_core::OsRng;
+        let sk = SecretKey::<crate::curve::CurveEngine>::gen(&mut rng, 2);
+        let ptr = to_secret_json_ptr(&sk).unwrap();
+        let expected = serde_json::to_string(&curve_tagged(&sk)).unwrap();
+        assert_eq!(ptr2str(ptr).unwrap(), expected);
+        drop_secret_ptr(ptr);
+    }
+
+    #[test]
+    fn test_c_api_curve_mismatch() {
+        let mut secret_key = CPecdkSecretKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(pecdkGenSecretKey(2, &mut secret_key), CErrorCode::Success);
+        let json = ptr2str(secret_key.ptr).unwrap();
+        let tag = format!("{{\"curve\":{},", CurveEngine::CURVE_ID);
+        assert!(json.starts_with(&tag));
+        let other_tag = format!("{{\"curve\":{},", CurveEngine::CURVE_ID % 2 + 1);
+        let other_key = CPecdkSecretKey {
+            ptr: str2ptr(json.replacen(&tag, &other_tag, 1)).unwrap(),
+        };
+        let mut public_key = CPecdkPublicKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenPublicKey(other_key.clone(), &mut public_key),
+            CErrorCode::CurveMismatch
+        );
+        assert!(public_key.ptr.is_null());
+        let untagged_key = CPecdkSecretKey {
+            ptr: str2ptr(json[tag.len() + "\"value\":".len()..json.len() - 1].to_string()).unwrap(),
+        };
+        assert_eq!(
+            pecdkGenPublicKey(untagged_key.clone(), &mut public_key),
+            CErrorCode::InvalidObject
+        );
+        pecdkFreeSecretKey(secret_key);
+        pecdkFreeSecretKey(other_key);
+        pecdkFreeSecretKey(untagged_key);
+    }
+
+    #[test]
+    fn test_c_api_catch_panic() {
+        let code = ffi_call("test_c_api_catch_panic", || panic!("unexpected"));
+        assert_eq!(code, CErrorCode::Panic);
+        let message = ptr2str(lastErrorMessage()).unwrap();
+        assert_eq!(message, "test_c_api_catch_panic: unexpected");
+    }
+
+    #[test]
+    fn test_c_api_from_seed() {
+        let passphrase = b"correct horse battery staple";
+        let salt = b"rust-searchable-pke";
+        let mut seed = crate::seed::CSeed { bytes: [0; 32] };
+        assert_eq!(
+            crate::seed::seedFromPassphrase(
+                passphrase.as_ptr(),
+                passphrase.len(),
+                salt.as_ptr(),
+                salt.len(),
+                &mut seed
+            ),
+            CErrorCode::Success
+        );
+        assert_eq!(
+            seed.bytes,
+            crate::seed::seed_from_passphrase(passphrase, salt)
+        );
+
+        let mut secret_keys = vec![];
+        for _ in 0..2 {
+            let mut secret_key = CPecdkSecretKey {
+                ptr: ptr::null_mut(),
+            };
+            assert_eq!(
+                pecdkGenSecretKeyFromSeed(seed, 2, &mut secret_key),
+                CErrorCode::Success
+            );
+            secret_keys.push(ptr2str(secret_key.ptr).unwrap().to_string());
+            pecdkFreeSecretKey(secret_key);
+        }
+        assert_eq!(secret_keys[0], secret_keys[1]);
+    }
+
+    #[test]
+    fn test_c_api_grapheme_prefix_boundary() {
+        let mut secret_key = CPecdkSecretKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(pecdkGenSecretKey(4, &mut secret_key), CErrorCode::Success);
+        let mut public_key = CPecdkPublicKey {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            pecdkGenPublicKey(secret_key.clone(), &mut public_key),
+            CErrorCode::Success
+        );
+        let region_name = str2ptr("region".to_string()).unwrap();
+        let string = str2ptr("アメンボ".to_string()).unwrap();
+        let mut ciphertext = CPecdkCiphertext {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            crate::expressions::genCiphertextForGraphemePrefixSearch(
+                public_key.clone(),
+                region_name,
+                string,
+                &mut ciphertext
+            ),
+            CErrorCode::Success
+        );
+
+        let prefix = "アメ".as_bytes();
+        let mut trapdoor = CPecdkTrapdoor {
+            ptr: ptr::null_mut(),
+        };
+        assert_eq!(
+            crate::expressions::genTrapdoorForGraphemePrefixSearch(
+                secret_key.clone(),
+                region_name,
+                prefix.as_ptr(),
+                4,
+                &mut trapdoor
+            ),
+            CErrorCode::InvalidArgument
+        );
+        assert!(trapdoor.ptr.is_null());
+        assert_eq!(
+            crate::expressions::genTrapdoorForGraphemePrefixSearch(
+                secret_key.clone(),
+                region_name,
+                prefix.as_ptr(),
+                prefix.len(),
+                &mut trapdoor
+            ),
+            CErrorCode::Success
+        );
+        let mut result = false;
+        assert_eq!(
+            pecdkTest(ciphertext.clone(), trapdoor.clone(), &mut result),
+            CErrorCode::Success
+        );
+        assert!(result);
+
+        pecdkFreeSecretKey(secret_key);
+        pecdkFreePublicKey(public_key);
+        pecdkFreeCiphertext(ciphertext);
+        pecdkFreeTrapdoor(trapdoor);
+        drop_ptr(region_name);
+        drop_ptr(string);
+    }
+}
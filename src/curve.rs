@@ -0,0 +1,31 @@
+//! The pairing curve used by the C API, selected through a cargo feature.
+//! The Rust API stays generic over `E: EngineId`; the chosen curve's id is
+//! written into every binary encoding, so objects from another backend are rejected.
+//!
+//! `bls12_381` selects `paired`'s BLS12-381 and `bn254` selects the arkworks-backed
+//! BN254 of the `bn254` module. Exactly one of them must be enabled, so `bn254` needs
+//! `--no-default-features`. Every C API object also records the curve id next to its JSON.
+
+#[cfg(not(any(feature = "bls12_381", feature = "bn254")))]
+compile_error!("a pairing curve feature must be enabled: `bls12_381` or `bn254`");
+#[cfg(all(feature = "bls12_381", feature = "bn254"))]
+compile_error!(
+    "`bls12_381` and `bn254` are mutually exclusive; build with `--no-default-features` for `bn254`"
+);
+
+#[cfg(feature = "bls12_381")]
+pub type CurveEngine = paired::bls12_381::Bls12;
+#[cfg(feature = "bls12_381")]
+pub type CurveFr = paired::bls12_381::Fr;
+
+#[cfg(all(feature = "bn254", not(feature = "bls12_381")))]
+pub type CurveEngine = crate::bn254::Bn254;
+#[cfg(all(feature = "bn254", not(feature = "bls12_381")))]
+pub type CurveFr = crate::bn254::Fr;
+
+#[cfg(feature = "c_api")]
+#[no_mangle]
+pub extern "C" fn curveId() -> u8 {
+    use crate::EngineId;
+    CurveEngine::CURVE_ID
+}
@@ -0,0 +1,182 @@
+use crate::c_utils::*;
+use crate::curve::CurveEngine;
+use crate::dpeks::*;
+use rand_core::OsRng;
+use std::os::raw::c_char;
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksServerSecretKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksServerPublicKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksSecretKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksPublicKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksCiphertext {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CDpeksTrapdoor {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksGenServerSecretKey(
+    server_secret_key: *mut CDpeksServerSecretKey,
+) -> CErrorCode {
+    ffi_output("dpeksGenServerSecretKey", server_secret_key, || {
+        let mut rng = OsRng;
+        let sk = ServerSecretKey::<CurveEngine>::gen(&mut rng);
+        Ok(CDpeksServerSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksGenServerPublicKey(
+    server_secret_key: CDpeksServerSecretKey,
+    server_public_key: *mut CDpeksServerPublicKey,
+) -> CErrorCode {
+    ffi_output("dpeksGenServerPublicKey", server_public_key, || {
+        let sk = from_json_ptr::<ServerSecretKey<CurveEngine>>(server_secret_key.ptr)?;
+        let pk = sk.into_public_key();
+        Ok(CDpeksServerPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksGenSecretKey(secret_key: *mut CDpeksSecretKey) -> CErrorCode {
+    ffi_output("dpeksGenSecretKey", secret_key, || {
+        let mut rng = OsRng;
+        let sk = SecretKey::<CurveEngine>::gen(&mut rng);
+        Ok(CDpeksSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksGenPublicKey(
+    secret_key: CDpeksSecretKey,
+    public_key: *mut CDpeksPublicKey,
+) -> CErrorCode {
+    ffi_output("dpeksGenPublicKey", public_key, || {
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let pk = sk.into_public_key();
+        Ok(CDpeksPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksEncryptKeyword(
+    server_public_key: CDpeksServerPublicKey,
+    public_key: CDpeksPublicKey,
+    keyword: *mut c_char,
+    ciphertext: *mut CDpeksCiphertext,
+) -> CErrorCode {
+    ffi_output("dpeksEncryptKeyword", ciphertext, || {
+        let mut rng = OsRng;
+        let server_pk = from_json_ptr::<ServerPublicKey<CurveEngine>>(server_public_key.ptr)?;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let ct = pk
+            .encrypt(&server_pk, keyword, &mut rng)
+            .map_err(CError::crypto)?;
+        Ok(CDpeksCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksGenTrapdoor(
+    server_public_key: CDpeksServerPublicKey,
+    secret_key: CDpeksSecretKey,
+    keyword: *mut c_char,
+    trapdoor: *mut CDpeksTrapdoor,
+) -> CErrorCode {
+    ffi_output("dpeksGenTrapdoor", trapdoor, || {
+        let mut rng = OsRng;
+        let server_pk = from_json_ptr::<ServerPublicKey<CurveEngine>>(server_public_key.ptr)?;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let td = sk
+            .gen_trapdoor(&server_pk, keyword, &mut rng)
+            .map_err(CError::crypto)?;
+        Ok(CDpeksTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksTest(
+    server_secret_key: CDpeksServerSecretKey,
+    ciphertext: CDpeksCiphertext,
+    trapdoor: CDpeksTrapdoor,
+    result: *mut bool,
+) -> CErrorCode {
+    ffi_output("dpeksTest", result, || {
+        let server_sk = from_json_ptr::<ServerSecretKey<CurveEngine>>(server_secret_key.ptr)?;
+        let ct = from_json_ptr::<Ciphertext<CurveEngine>>(ciphertext.ptr)?;
+        let td = from_json_ptr::<Trapdoor<CurveEngine>>(trapdoor.ptr)?;
+        server_sk.test(&ct, &td).map_err(CError::crypto)
+    })
+}
+
+/// Overwrites the serialized secret key with zeros before freeing it.
+#[no_mangle]
+pub extern "C" fn dpeksFreeServerSecretKey(server_secret_key: CDpeksServerSecretKey) {
+    drop_secret_ptr(server_secret_key.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksFreeServerPublicKey(server_public_key: CDpeksServerPublicKey) {
+    drop_ptr(server_public_key.ptr);
+}
+
+/// Overwrites the serialized secret key with zeros before freeing it.
+#[no_mangle]
+pub extern "C" fn dpeksFreeSecretKey(secret_key: CDpeksSecretKey) {
+    drop_secret_ptr(secret_key.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksFreePublicKey(public_key: CDpeksPublicKey) {
+    drop_ptr(public_key.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksFreeCiphertext(ciphertext: CDpeksCiphertext) {
+    drop_ptr(ciphertext.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn dpeksFreeTrapdoor(trapdoor: CDpeksTrapdoor) {
+    drop_ptr(trapdoor.ptr);
+}
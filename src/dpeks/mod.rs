@@ -0,0 +1,272 @@
+//! Public-key encryption with keyword search and a designated tester (dPEKS).
+//! Ciphertexts are bound to the server's public key as well as the receiver's,
+//! so a trapdoor can only be tested with the server's secret key.
+//!
+//! A trapdoor is `(T1, T2) = (Y^s, H(w)^(1/alpha) * g^s)` for the server's `Y = g^beta` and a fresh `s`.
+//! Without `beta`, the blinding `g^s` cannot be removed, so anyone holding a leaked trapdoor
+//! cannot check a guessed keyword `w'` against the receiver's public key.
+//! The server itself can still test guesses, since it can unblind the trapdoor.
+
+#[cfg(feature = "c_api")]
+mod c_api;
+
+use crate::hashes::*;
+use crate::wipe::wipe;
+
+#[cfg(feature = "c_api")]
+pub use c_api::*;
+
+use fff::Field;
+use groupy::{CurveAffine, CurveProjective};
+use paired::Engine;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DPEKSError {
+    #[error("The secret key is zero")]
+    ZeroSecretKey,
+    #[error(transparent)]
+    ECHashError(#[from] ECHashError),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ServerSecretKey<E: Engine> {
+    beta: E::Fr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerPublicKey<E: Engine> {
+    y: E::G2Affine,
+    y1: E::G1Affine,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretKey<E: Engine> {
+    alpha: E::Fr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKey<E: Engine> {
+    y: E::G2Affine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ciphertext<E: Engine> {
+    a: E::G2Affine,
+    b: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trapdoor<E: Engine> {
+    t1: E::G1Affine,
+    t2: E::G1Affine,
+}
+
+impl<E: Engine> ServerSecretKey<E> {
+    pub fn gen<R: RngCore>(rng: &mut R) -> Self {
+        let beta = <E::Fr as Field>::random(rng);
+        Self { beta }
+    }
+
+    pub fn into_public_key(&self) -> ServerPublicKey<E> {
+        let y = E::G2Affine::one().mul(self.beta).into_affine();
+        let y1 = E::G1Affine::one().mul(self.beta).into_affine();
+        ServerPublicKey { y, y1 }
+    }
+
+    /// Tests whether `ct` encrypts the keyword of `trapdoor`.
+    pub fn test(&self, ct: &Ciphertext<E>, trapdoor: &Trapdoor<E>) -> Result<bool, DPEKSError> {
+        let beta_inv = self.beta.inverse().ok_or(DPEKSError::ZeroSecretKey)?;
+        // t2 - t1 / beta = H(w)^(1/alpha)
+        let mut t = trapdoor.t2.into_projective();
+        t.sub_assign(&trapdoor.t1.mul(beta_inv));
+        let pairinged = E::pairing(t, ct.a.mul(self.beta));
+        let hashed = hash_field2bytes::<E>(pairinged)?;
+        Ok(digest_eq(&hashed, &ct.b))
+    }
+}
+
+impl<E: Engine> fmt::Debug for ServerSecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerSecretKey").finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Drop for ServerSecretKey<E> {
+    fn drop(&mut self) {
+        wipe(
+            std::slice::from_mut(&mut self.beta),
+            <E::Fr as Field>::zero(),
+        );
+    }
+}
+
+impl<E: Engine> ServerPublicKey<E> {
+    pub fn from_secret_key(secret_key: &ServerSecretKey<E>) -> Self {
+        secret_key.into_public_key()
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    pub fn gen<R: RngCore>(rng: &mut R) -> Self {
+        let alpha = <E::Fr as Field>::random(rng);
+        Self { alpha }
+    }
+
+    pub fn into_public_key(&self) -> PublicKey<E> {
+        let y = E::G2Affine::one().mul(self.alpha).into_affine();
+        PublicKey { y }
+    }
+
+    /// Generates a trapdoor that only the server of `server_public_key` can use.
+    pub fn gen_trapdoor<R: RngCore>(
+        &self,
+        server_public_key: &ServerPublicKey<E>,
+        keyword: &[u8],
+        rng: &mut R,
+    ) -> Result<Trapdoor<E>, DPEKSError> {
+        let alpha_inv = self.alpha.inverse().ok_or(DPEKSError::ZeroSecretKey)?;
+        let s = <E::Fr as Field>::random(rng);
+        let hashed_w = hash_bytes2point::<E>(keyword).into_affine();
+        let t1 = server_public_key.y1.mul(s).into_affine();
+        let mut t2 = hashed_w.mul(alpha_inv);
+        t2.add_assign(&E::G1Affine::one().mul(s));
+        let t2 = t2.into_affine();
+        Ok(Trapdoor { t1, t2 })
+    }
+}
+
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        wipe(
+            std::slice::from_mut(&mut self.alpha),
+            <E::Fr as Field>::zero(),
+        );
+    }
+}
+
+impl<E: Engine> PublicKey<E> {
+    pub fn from_secret_key(secret_key: &SecretKey<E>) -> Self {
+        secret_key.into_public_key()
+    }
+
+    pub fn encrypt<R: RngCore>(
+        &self,
+        server_public_key: &ServerPublicKey<E>,
+        keyword: &[u8],
+        rng: &mut R,
+    ) -> Result<Ciphertext<E>, DPEKSError> {
+        let r = <E::Fr as Field>::random(rng);
+        let a = self.y.mul(r).into_affine();
+        let mut hashed_w = hash_bytes2point::<E>(keyword);
+        hashed_w.mul_assign(r);
+        let pairinged = E::pairing(hashed_w, server_public_key.y.into_projective());
+        let b = hash_field2bytes::<E>(pairinged)?;
+        Ok(Ciphertext { a, b })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::Bls12;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_dpeks_designated_tester() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let server_secret_key = ServerSecretKey::<Bls12>::gen(&mut rng);
+        let server_public_key = server_secret_key.into_public_key();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng);
+        let public_key = secret_key.into_public_key();
+
+        let ct1 = public_key
+            .encrypt(&server_public_key, b"keyword1", &mut rng)
+            .unwrap();
+        let ct2 = public_key
+            .encrypt(&server_public_key, b"keyword2", &mut rng)
+            .unwrap();
+        let trapdoor = secret_key
+            .gen_trapdoor(&server_public_key, b"keyword1", &mut rng)
+            .unwrap();
+        assert!(server_secret_key.test(&ct1, &trapdoor).unwrap());
+        assert!(!server_secret_key.test(&ct2, &trapdoor).unwrap());
+
+        // Another server cannot test the trapdoor.
+        let other_server = ServerSecretKey::<Bls12>::gen(&mut rng);
+        assert!(!other_server.test(&ct1, &trapdoor).unwrap());
+
+        let json = serde_json::to_string(&ct1).unwrap();
+        let decoded: Ciphertext<Bls12> = serde_json::from_str(&json).unwrap();
+        assert!(server_secret_key.test(&decoded, &trapdoor).unwrap());
+    }
+
+    #[test]
+    fn test_dpeks_public_key_guessing() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let server_secret_key = ServerSecretKey::<Bls12>::gen(&mut rng);
+        let server_public_key = server_secret_key.into_public_key();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng);
+        let public_key = secret_key.into_public_key();
+        let trapdoor = secret_key
+            .gen_trapdoor(&server_public_key, b"keyword1", &mut rng)
+            .unwrap();
+
+        // e(t, y) == e(H(w), g) would confirm a guess for an unblinded t = H(w)^(1/alpha).
+        let guess = |t: &<Bls12 as Engine>::G1Affine, keyword: &[u8]| {
+            Bls12::pairing(t.into_projective(), public_key.y.into_projective())
+                == Bls12::pairing(
+                    hash_bytes2point::<Bls12>(keyword),
+                    <Bls12 as Engine>::G2Affine::one().into_projective(),
+                )
+        };
+        assert!(!guess(&trapdoor.t1, b"keyword1"));
+        assert!(!guess(&trapdoor.t2, b"keyword1"));
+
+        // Trapdoors for the same keyword are unlinkable.
+        let again = secret_key
+            .gen_trapdoor(&server_public_key, b"keyword1", &mut rng)
+            .unwrap();
+        assert_ne!(trapdoor.t1, again.t1);
+        assert_ne!(trapdoor.t2, again.t2);
+
+        // The server can remove the blinding, so the check passes for it.
+        let beta_inv = server_secret_key.beta.inverse().unwrap();
+        let mut unblinded = trapdoor.t2.into_projective();
+        unblinded.sub_assign(&trapdoor.t1.mul(beta_inv));
+        assert!(guess(&unblinded.into_affine(), b"keyword1"));
+    }
+
+    #[test]
+    fn test_dpeks_secret_keys_debug_redacted() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let server_secret_key = ServerSecretKey::<Bls12>::gen(&mut rng);
+        let debug = format!("{:?}", server_secret_key);
+        assert_eq!(debug, "ServerSecretKey { .. }");
+        assert!(!debug.contains(&server_secret_key.beta.to_string()));
+
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng);
+        let debug = format!("{:?}", secret_key);
+        assert_eq!(debug, "SecretKey { .. }");
+        assert!(!debug.contains(&secret_key.alpha.to_string()));
+    }
+}
@@ -1,9 +1,7 @@
 use super::*;
 use crate::c_utils::*;
+use crate::curve::{CurveEngine, CurveFr};
 use crate::pecdk::*;
-use core::slice;
-use errno::{set_errno, Errno};
-use paired::bls12_381::{Bls12, Fr};
 use rand_core::OsRng;
 use std::collections::HashMap;
 use std::os::raw::c_char;
@@ -16,40 +14,46 @@ pub extern "C" fn genCiphertextForFieldSearch(
     num_fields: usize,
     fields: *mut *mut c_char,
     vals: *mut *mut c_char,
-) -> CPecdkCiphertext {
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForFieldSearch", ciphertext, || {
+        let field_val_map = ptrs2field_val_map(num_fields, fields, vals)?;
+        gen_ciphertext_for_field_search_generic(public_key, region_name, field_val_map)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genCiphertextForFieldSearchBytes(
+    public_key: CPecdkPublicKey,
+    region_name: *mut c_char,
+    num_fields: usize,
+    fields: *const *const u8,
+    field_lens: *const usize,
+    vals: *const *const u8,
+    val_lens: *const usize,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForFieldSearchBytes", ciphertext, || {
+        let field_val_map =
+            bytes_ptrs2field_val_map(num_fields, fields, field_lens, vals, val_lens)?;
+        gen_ciphertext_for_field_search_generic(public_key, region_name, field_val_map)
+    })
+}
+
+fn gen_ciphertext_for_field_search_generic(
+    public_key: CPecdkPublicKey,
+    region_name: *mut c_char,
+    field_val_map: HashMap<Vec<u8>, Vec<u8>>,
+) -> Result<CPecdkCiphertext, CError> {
     let mut rng = OsRng;
-    let pk = match serde_json::from_str::<PublicKey<Bls12>>(&ptr2str(public_key.ptr)) {
-        Ok(pk) => pk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let fields_slice = unsafe { slice::from_raw_parts(fields, num_fields) };
-    let fields = fields_slice
-        .into_iter()
-        .map(|field| ptr2str(*field).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
-    let vals_slice = unsafe { slice::from_raw_parts(vals, num_fields) };
-    let vals = vals_slice
-        .into_iter()
-        .map(|val| ptr2str(*val).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
-    let mut field_val_map = HashMap::<Vec<u8>, Vec<u8>>::new();
-    for (field, val) in fields.into_iter().zip(vals) {
-        field_val_map.insert(field, val);
-    }
-    let ct = gen_ciphertext_for_field_search::<_, Fr, _>(&pk, region_name, field_val_map, &mut rng)
-        .expect("Fail to generate a ciphertext in c_gen_ciphertext_for_field_search");
-    let ct_str = serde_json::to_string(&ct).expect(
-        "Fail to convert a ciphertext to a string in c_gen_ciphertext_for_field_generic_search",
-    );
-    CPecdkCiphertext {
-        ptr: str2ptr(ct_str),
-    }
+    let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+    let region_name = ptr2str(region_name)?;
+    let ct =
+        gen_ciphertext_for_field_search::<_, CurveFr, _>(&pk, region_name, field_val_map, &mut rng)
+            .map_err(CError::crypto)?;
+    Ok(CPecdkCiphertext {
+        ptr: to_json_ptr(&ct)?,
+    })
 }
 
 #[no_mangle]
@@ -59,15 +63,40 @@ pub extern "C" fn genTrapdoorForFieldAndSearch(
     num_fields: usize,
     fields: *mut *mut c_char,
     vals: *mut *mut c_char,
-) -> CPecdkTrapdoor {
-    gen_trapdoor_for_field_search_generic(
-        secret_key,
-        region_name,
-        num_fields,
-        fields,
-        vals,
-        SearchSym::AND,
-    )
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForFieldAndSearch", trapdoor, || {
+        let field_val_map = ptrs2field_val_map(num_fields, fields, vals)?;
+        gen_trapdoor_for_field_search_generic(
+            secret_key,
+            region_name,
+            field_val_map,
+            SearchSym::AND,
+        )
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genTrapdoorForFieldAndSearchBytes(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    num_fields: usize,
+    fields: *const *const u8,
+    field_lens: *const usize,
+    vals: *const *const u8,
+    val_lens: *const usize,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForFieldAndSearchBytes", trapdoor, || {
+        let field_val_map =
+            bytes_ptrs2field_val_map(num_fields, fields, field_lens, vals, val_lens)?;
+        gen_trapdoor_for_field_search_generic(
+            secret_key,
+            region_name,
+            field_val_map,
+            SearchSym::AND,
+        )
+    })
 }
 
 #[no_mangle]
@@ -77,66 +106,78 @@ pub extern "C" fn genTrapdoorForFieldOrSearch(
     num_fields: usize,
     fields: *mut *mut c_char,
     vals: *mut *mut c_char,
-) -> CPecdkTrapdoor {
-    gen_trapdoor_for_field_search_generic(
-        secret_key,
-        region_name,
-        num_fields,
-        fields,
-        vals,
-        SearchSym::OR,
-    )
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForFieldOrSearch", trapdoor, || {
+        let field_val_map = ptrs2field_val_map(num_fields, fields, vals)?;
+        gen_trapdoor_for_field_search_generic(secret_key, region_name, field_val_map, SearchSym::OR)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genTrapdoorForFieldOrSearchBytes(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    num_fields: usize,
+    fields: *const *const u8,
+    field_lens: *const usize,
+    vals: *const *const u8,
+    val_lens: *const usize,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForFieldOrSearchBytes", trapdoor, || {
+        let field_val_map =
+            bytes_ptrs2field_val_map(num_fields, fields, field_lens, vals, val_lens)?;
+        gen_trapdoor_for_field_search_generic(secret_key, region_name, field_val_map, SearchSym::OR)
+    })
 }
 
 fn gen_trapdoor_for_field_search_generic(
     secret_key: CPecdkSecretKey,
     region_name: *mut c_char,
+    field_val_map: HashMap<Vec<u8>, Vec<u8>>,
+    sym: SearchSym,
+) -> Result<CPecdkTrapdoor, CError> {
+    let mut rng = OsRng;
+    let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+    let region_name = ptr2str(region_name)?;
+    let td = gen_trapdoor_for_field_search::<_, CurveFr, _>(
+        &sk,
+        region_name,
+        field_val_map,
+        sym,
+        &mut rng,
+    )
+    .map_err(CError::crypto)?;
+    Ok(CPecdkTrapdoor {
+        ptr: to_json_ptr(&td)?,
+    })
+}
+
+fn ptrs2field_val_map(
     num_fields: usize,
     fields: *mut *mut c_char,
     vals: *mut *mut c_char,
-    sym: SearchSym,
-) -> CPecdkTrapdoor {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let fields_slice = unsafe { slice::from_raw_parts(fields, num_fields) };
-    let fields = fields_slice
-        .into_iter()
-        .map(|field| ptr2str(*field).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
-    let vals_slice = unsafe { slice::from_raw_parts(vals, num_fields) };
-    let vals = vals_slice
-        .into_iter()
-        .map(|val| ptr2str(*val).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
+) -> Result<HashMap<Vec<u8>, Vec<u8>>, CError> {
+    let fields = ptrs2strs(fields, num_fields)?;
+    let vals = ptrs2strs(vals, num_fields)?;
     let mut field_val_map = HashMap::<Vec<u8>, Vec<u8>>::new();
     for (field, val) in fields.into_iter().zip(vals) {
-        field_val_map.insert(field, val);
-    }
-    let td = match sym {
-        SearchSym::AND => {
-            gen_trapdoor_for_field_and_search::<_, Fr, _>(&sk, region_name, field_val_map, &mut rng)
-                .expect("Fail to generate a trapdoor in c_gen_trapdoor_for_field_and_search")
-        }
-        SearchSym::OR => {
-            gen_trapdoor_for_field_or_search::<_, Fr, _>(&sk, region_name, field_val_map, &mut rng)
-                .expect("Fail to generate a trapdoor in c_gen_trapdoor_for_field_or_search")
-        }
-    };
-    let td_str = serde_json::to_string(&td).expect(
-        "Fail to convert a ciphertext to a string in c_gen_trapdoor_for_field_search_generic",
-    );
-    CPecdkTrapdoor {
-        ptr: str2ptr(td_str),
+        field_val_map.insert(field.as_bytes().to_vec(), val.as_bytes().to_vec());
     }
+    Ok(field_val_map)
+}
+
+fn bytes_ptrs2field_val_map(
+    num_fields: usize,
+    fields: *const *const u8,
+    field_lens: *const usize,
+    vals: *const *const u8,
+    val_lens: *const usize,
+) -> Result<HashMap<Vec<u8>, Vec<u8>>, CError> {
+    let fields = ptrs2bytes_vec(fields, field_lens, num_fields)?;
+    let vals = ptrs2bytes_vec(vals, val_lens, num_fields)?;
+    Ok(fields.into_iter().zip(vals).collect())
 }
 
 #[no_mangle]
@@ -144,26 +185,20 @@ pub extern "C" fn genCiphertextForPrefixSearch(
     public_key: CPecdkPublicKey,
     region_name: *mut c_char,
     string: *mut c_char,
-) -> CPecdkCiphertext {
-    let mut rng = OsRng;
-    let pk = match serde_json::from_str::<PublicKey<Bls12>>(&ptr2str(public_key.ptr)) {
-        Ok(pk) => pk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let string = ptr2str(string);
-    let ct = gen_ciphertext_for_prefix_search::<_, Fr, _>(&pk, region_name, string, &mut rng)
-        .expect("Fail to generate a ciphertext in c_gen_ciphertext_for_prefix_search");
-    let ct_str = serde_json::to_string(&ct)
-        .expect("Fail to convert a ciphertext to a string in c_gen_ciphertext_for_prefix_search");
-    CPecdkCiphertext {
-        ptr: str2ptr(ct_str),
-    }
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForPrefixSearch", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let ct =
+            gen_ciphertext_for_prefix_search::<_, CurveFr, _>(&pk, region_name, string, &mut rng)
+                .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
 }
 
 #[no_mangle]
@@ -171,27 +206,20 @@ pub extern "C" fn genTrapdoorForPrefixSearch(
     secret_key: CPecdkSecretKey,
     region_name: *mut c_char,
     prefix: *mut c_char,
-) -> CPecdkTrapdoor {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let prefix = ptr2str(prefix);
-
-    let td = gen_trapdoor_for_prefix_search::<_, Fr, _>(&sk, region_name, prefix, &mut rng)
-        .expect("Fail to generate a trapdoor in c_gen_trapdoor_for_prefix_search");
-    let td_str = serde_json::to_string(&td)
-        .expect("Fail to convert a ciphertext to a string in c_gen_trapdoor_for_prefix_search");
-    CPecdkTrapdoor {
-        ptr: str2ptr(td_str),
-    }
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForPrefixSearch", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let prefix = ptr2str(prefix)?;
+        let td =
+            gen_trapdoor_for_prefix_search::<_, CurveFr, _>(&sk, region_name, prefix, &mut rng)
+                .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
 }
 
 #[no_mangle]
@@ -199,28 +227,195 @@ pub extern "C" fn genTrapdoorForPrefixSearchExact(
     secret_key: CPecdkSecretKey,
     region_name: *mut c_char,
     string: *mut c_char,
-) -> CPecdkTrapdoor {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let string = ptr2str(string);
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForPrefixSearchExact", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let td = gen_trapdoor_for_prefix_search_exact::<_, CurveFr, _>(
+            &sk,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
 
-    let td = gen_trapdoor_for_prefix_search_exact::<_, Fr, _>(&sk, region_name, string, &mut rng)
-        .expect("Fail to generate a trapdoor in c_gen_trapdoor_for_prefix_search_exact");
-    let td_str = serde_json::to_string(&td).expect(
-        "Fail to convert a ciphertext to a string in c_gen_trapdoor_for_prefix_search_exact",
-    );
-    CPecdkTrapdoor {
-        ptr: str2ptr(td_str),
-    }
+#[no_mangle]
+pub extern "C" fn genCiphertextForGraphemePrefixSearch(
+    public_key: CPecdkPublicKey,
+    region_name: *mut c_char,
+    string: *mut c_char,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForGraphemePrefixSearch", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let ct = gen_ciphertext_for_grapheme_prefix_search::<_, CurveFr, _>(
+            &pk,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
+}
+
+/// The prefix is given as bytes and rejected with `InvalidArgument`
+/// unless it is valid UTF-8 ending on a character boundary.
+#[no_mangle]
+pub extern "C" fn genTrapdoorForGraphemePrefixSearch(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    prefix: *const u8,
+    prefix_len: usize,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForGraphemePrefixSearch", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let prefix = grapheme_prefix_from_bytes(ptr2bytes(prefix, prefix_len)?)
+            .map_err(|err| CError::new(CErrorCode::InvalidArgument, err))?;
+        let td = gen_trapdoor_for_grapheme_prefix_search::<_, CurveFr, _>(
+            &sk,
+            region_name,
+            prefix,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genTrapdoorForGraphemePrefixSearchExact(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    string: *mut c_char,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForGraphemePrefixSearchExact", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let td = gen_trapdoor_for_grapheme_prefix_search_exact::<_, CurveFr, _>(
+            &sk,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genCiphertextForSubstringSearch(
+    public_key: CPecdkPublicKey,
+    region_name: *mut c_char,
+    string: *mut c_char,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForSubstringSearch", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let ct = gen_ciphertext_for_substring_search::<_, CurveFr, _>(
+            &pk,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genTrapdoorForSubstringSearch(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    substring: *mut c_char,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForSubstringSearch", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let substring = ptr2str(substring)?;
+        let td = gen_trapdoor_for_substring_search::<_, CurveFr, _>(
+            &sk,
+            region_name,
+            substring,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genCiphertextForSuffixSearch(
+    public_key: CPecdkPublicKey,
+    region_name: *mut c_char,
+    string: *mut c_char,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForSuffixSearch", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let string = ptr2str(string)?;
+        let ct =
+            gen_ciphertext_for_suffix_search::<_, CurveFr, _>(&pk, region_name, string, &mut rng)
+                .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn genTrapdoorForSuffixSearch(
+    secret_key: CPecdkSecretKey,
+    region_name: *mut c_char,
+    suffix: *mut c_char,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForSuffixSearch", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let suffix = ptr2str(suffix)?;
+        let td =
+            gen_trapdoor_for_suffix_search::<_, CurveFr, _>(&sk, region_name, suffix, &mut rng)
+                .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
 }
 
 #[no_mangle]
@@ -229,34 +424,25 @@ pub extern "C" fn genCiphertextForRangeSearch(
     region_name: *mut c_char,
     bit_size: usize,
     val: c_uint,
-) -> CPecdkCiphertext {
-    let mut rng = OsRng;
-    let pk = match serde_json::from_str::<PublicKey<Bls12>>(&ptr2str(public_key.ptr)) {
-        Ok(pk) => pk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let region_name = ptr2str(region_name);
-    let val: u64 = match val.try_into() {
-        Ok(num) => num,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let ct = gen_ciphertext_for_range_search::<_, Fr, _>(&pk, region_name, bit_size, val, &mut rng)
-        .expect("Fail to generate a ciphertext in c_gen_ciphertext_for_range_search");
-    let ct_str = serde_json::to_string(&ct)
-        .expect("Fail to convert a ciphertext to a string in c_gen_ciphertext_for_range_search");
-    CPecdkCiphertext {
-        ptr: str2ptr(ct_str),
-    }
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("genCiphertextForRangeSearch", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        let val = u64::from(val);
+        let ct = gen_ciphertext_for_range_search::<_, CurveFr, _>(
+            &pk,
+            region_name,
+            bit_size,
+            val,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
 }
 
 #[no_mangle]
@@ -266,42 +452,31 @@ pub extern "C" fn genTrapdoorForRangeSearch(
     min: c_uint,
     max: c_uint,
     bit_size: usize,
-) -> CPecdkTrapdoor {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("genTrapdoorForRangeSearch", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let region_name = ptr2str(region_name)?;
+        if min > max {
+            return Err(CError::new(
+                CErrorCode::InvalidArgument,
+                "min is larger than max",
+            ));
         }
-    };
-    let region_name = ptr2str(region_name);
-    let min: u64 = match min.try_into() {
-        Ok(num) => num,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let max: u64 = match max.try_into() {
-        Ok(num) => num,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let td =
-        gen_trapdoor_for_range_search::<_, Fr, _>(&sk, region_name, min, max, bit_size, &mut rng)
-            .expect("Fail to generate a trapdoor in c_gen_trapdoor_for_range_search");
-    let td_str = serde_json::to_string(&td)
-        .expect("Fail to convert a ciphertext to a string in c_gen_trapdoor_for_range_search");
-    CPecdkTrapdoor {
-        ptr: str2ptr(td_str),
-    }
+        let min = u64::from(min);
+        let max = u64::from(max);
+        let td = gen_trapdoor_for_range_search::<_, CurveFr, _>(
+            &sk,
+            region_name,
+            min,
+            max,
+            bit_size,
+            &mut rng,
+        )
+        .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
 }
@@ -18,9 +18,8 @@ pub fn gen_ciphertext_for_field_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
     if n_bytes > max_bytes {
         return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
     }
-    let n_remaining = max_bytes - n_bytes;
 
-    let mut keywords = field_val_map
+    let keywords = field_val_map
         .into_iter()
         .map(|(field, val)| {
             concat_multi_bytes(vec![
@@ -31,9 +30,6 @@ pub fn gen_ciphertext_for_field_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
             ])
         })
         .collect::<Vec<Vec<u8>>>();
-    for _ in 0..n_remaining {
-        keywords.push(concat_multi_bytes(vec![region_name.as_bytes(), &vec![0u8]]));
-    }
     let ct = public_key.encrypt::<R, F>(keywords, rng)?;
     Ok(ct)
 }
@@ -68,7 +64,7 @@ pub fn gen_trapdoor_for_field_or_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
     )
 }
 
-fn gen_trapdoor_for_field_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+pub(crate) fn gen_trapdoor_for_field_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
     secret_key: &SecretKey<E>,
     region_name: &str,
     field_val_map: HashMap<Vec<u8>, Vec<u8>>,
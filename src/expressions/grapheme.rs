@@ -0,0 +1,218 @@
+//! Prefix search indexed by extended grapheme clusters instead of bytes.
+//!
+//! Each user-perceived character takes one keyword, so a string of `L` graphemes
+//! takes `L` keywords plus one terminator keyword when `L` is below the maximum.
+//! A prefix that splits a grapheme of the string, e.g. a base character without
+//! its combining mark, does not match.
+
+use super::utils::*;
+use super::{ExpError, GraphemePrefixError};
+use crate::pecdk::*;
+use crate::BaseROFr;
+use paired::Engine;
+use rand_core::RngCore;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn gen_ciphertext_for_grapheme_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    public_key: &PublicKey<E>,
+    region_name: &str,
+    string: &str,
+    rng: &mut R,
+) -> Result<Ciphertext<E>, ExpError<E>> {
+    let graphemes = string.graphemes(true).collect::<Vec<&str>>();
+    let n_graphemes = graphemes.len();
+    let max_graphemes = public_key.num_keyword();
+    if n_graphemes > max_graphemes {
+        return Err(ExpError::ExcessiveNumberOfKeywords(
+            n_graphemes,
+            max_graphemes,
+        ));
+    }
+
+    let mut keywords = grapheme_keywords(region_name, &graphemes);
+    if n_graphemes < max_graphemes {
+        keywords.push(grapheme_terminator_keyword(region_name, n_graphemes));
+    }
+    let ct = public_key.encrypt::<R, F>(keywords, rng)?;
+    Ok(ct)
+}
+
+pub fn gen_trapdoor_for_grapheme_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    prefix: &str,
+    rng: &mut R,
+) -> Result<Trapdoor<E>, ExpError<E>> {
+    let graphemes = prefix.graphemes(true).collect::<Vec<&str>>();
+    let max_graphemes = secret_key.num_keyword();
+    if graphemes.len() > max_graphemes {
+        return Err(ExpError::ExcessiveNumberOfKeywords(
+            graphemes.len(),
+            max_graphemes,
+        ));
+    }
+
+    let keywords = grapheme_keywords(region_name, &graphemes);
+    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
+    Ok(td)
+}
+
+pub fn gen_trapdoor_for_grapheme_prefix_search_exact<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    string: &str,
+    rng: &mut R,
+) -> Result<Trapdoor<E>, ExpError<E>> {
+    let graphemes = string.graphemes(true).collect::<Vec<&str>>();
+    let n_graphemes = graphemes.len();
+    let max_graphemes = secret_key.num_keyword();
+    if n_graphemes > max_graphemes {
+        return Err(ExpError::ExcessiveNumberOfKeywords(
+            n_graphemes,
+            max_graphemes,
+        ));
+    }
+
+    let mut keywords = grapheme_keywords(region_name, &graphemes);
+    if n_graphemes < max_graphemes {
+        keywords.push(grapheme_terminator_keyword(region_name, n_graphemes));
+    }
+    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
+    Ok(td)
+}
+
+/// Interprets `bytes` as a prefix, rejecting it unless it is valid UTF-8 ending on a character boundary.
+pub fn grapheme_prefix_from_bytes(bytes: &[u8]) -> Result<&str, GraphemePrefixError> {
+    std::str::from_utf8(bytes).map_err(|err| match err.error_len() {
+        // The input ends in the middle of a character.
+        None => GraphemePrefixError::NotCharBoundary(err.valid_up_to()),
+        Some(_) => GraphemePrefixError::InvalidUtf8(err.valid_up_to()),
+    })
+}
+
+fn grapheme_keywords(region_name: &str, graphemes: &[&str]) -> Vec<Vec<u8>> {
+    graphemes
+        .iter()
+        .enumerate()
+        .map(|(idx, grapheme)| {
+            concat_multi_bytes(vec![
+                region_name.as_bytes(),
+                &idx.to_be_bytes(),
+                &[4u8],
+                grapheme.as_bytes(),
+            ])
+        })
+        .collect()
+}
+
+fn grapheme_terminator_keyword(region_name: &str, idx: usize) -> Vec<u8> {
+    concat_multi_bytes(vec![region_name.as_bytes(), &idx.to_be_bytes(), &[5u8]])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_grapheme_prefix_non_ascii() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        // 12 characters in 36 bytes.
+        let string = "アメンボ赤いなあいうえお";
+        let n = 12;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_grapheme_prefix_non_ascii";
+        let ct = gen_ciphertext_for_grapheme_prefix_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .unwrap();
+        for (prefix, expected) in [
+            ("アメンボ", true),
+            ("アメンボ赤い", true),
+            ("アメンホ", false),
+        ] {
+            let trapdoor = gen_trapdoor_for_grapheme_prefix_search::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                prefix,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+        let trapdoor = gen_trapdoor_for_grapheme_prefix_search_exact::<_, Fr, _>(
+            &secret_key,
+            region_name,
+            string,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(trapdoor.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_grapheme_prefix_combining_mark() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 5;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_grapheme_prefix_combining_mark";
+        // "e" followed by a combining acute accent is one grapheme.
+        let ct = gen_ciphertext_for_grapheme_prefix_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            "cafe\u{301}s",
+            &mut rng,
+        )
+        .unwrap();
+        for (prefix, expected) in [("cafe\u{301}", true), ("caf", true), ("cafe", false)] {
+            let trapdoor = gen_trapdoor_for_grapheme_prefix_search::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                prefix,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+
+        let bytes = "アメ".as_bytes();
+        assert_eq!(grapheme_prefix_from_bytes(bytes).unwrap(), "アメ");
+        assert_eq!(
+            grapheme_prefix_from_bytes(&bytes[..4]),
+            Err(GraphemePrefixError::NotCharBoundary(3))
+        );
+    }
+
+    #[test]
+    fn test_grapheme_prefix_invalid_utf8() {
+        // A lone continuation byte after "ア", followed by a complete "メ".
+        let mut bytes = "ア".as_bytes().to_vec();
+        bytes.push(0x80);
+        bytes.extend_from_slice("メ".as_bytes());
+        assert_eq!(
+            grapheme_prefix_from_bytes(&bytes),
+            Err(GraphemePrefixError::InvalidUtf8(3))
+        );
+        // A truncated "メ" in the middle is invalid too, unlike one at the end.
+        let mut bytes = "メ".as_bytes()[..2].to_vec();
+        bytes.extend_from_slice("ア".as_bytes());
+        assert_eq!(
+            grapheme_prefix_from_bytes(&bytes),
+            Err(GraphemePrefixError::InvalidUtf8(0))
+        );
+    }
+}
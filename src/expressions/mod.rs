@@ -2,16 +2,22 @@
 mod c_api;
 
 mod fields_and_or;
+mod grapheme;
+mod normalize;
 mod prefix;
 mod range;
+mod substring;
 mod utils;
 
 #[cfg(feature = "c_api")]
 pub use c_api::*;
 
 pub use fields_and_or::*;
+pub use grapheme::*;
+pub use normalize::*;
 pub use prefix::*;
 pub use range::*;
+pub use substring::*;
 
 use crate::pecdk::PECDKError;
 use paired::Engine;
@@ -22,6 +28,19 @@ use thiserror::Error;
 pub enum ExpError<E: Engine> {
     #[error("The number of given keywords is {0}, but the max number is {1}")]
     ExcessiveNumberOfKeywords(usize, usize),
+    #[error("The query has {0} bytes, but at least {1} bytes are required")]
+    QueryTooShort(usize, usize),
+    #[error("The ciphertext is normalized with {0:?}, but the trapdoor with {1:?}")]
+    NormalizationMismatch(Normalization, Normalization),
     #[error(transparent)]
     PECDKError(#[from] PECDKError<E>),
 }
+
+/// Errors from `grapheme_prefix_from_bytes`.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GraphemePrefixError {
+    #[error("The bytes do not end on a character boundary; only the first {0} bytes are valid")]
+    NotCharBoundary(usize),
+    #[error("The bytes are not valid UTF-8 after the first {0} bytes")]
+    InvalidUtf8(usize),
+}
@@ -0,0 +1,278 @@
+//! Prefix search over normalized strings.
+//!
+//! The normalization is applied to both the encrypted string and the searched prefix,
+//! and is recorded in the returned ciphertexts and trapdoors.
+//! Testing a trapdoor against a ciphertext normalized differently is an error.
+//! Non-default settings are also mixed into the region name, so the underlying
+//! `Ciphertext` and `Trapdoor` of different settings never match each other.
+
+use super::prefix::*;
+use super::ExpError;
+use crate::pecdk::*;
+use crate::BaseROFr;
+use caseless::{default_case_fold_str, Caseless};
+use paired::Engine;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UnicodeForm {
+    /// The string is used as given.
+    #[default]
+    Unchanged,
+    Nfc,
+    Nfkc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Normalization {
+    pub form: UnicodeForm,
+    /// Applies full Unicode case folding together with the Unicode normalization.
+    pub case_fold: bool,
+}
+
+impl Normalization {
+    pub fn new(form: UnicodeForm, case_fold: bool) -> Self {
+        Self { form, case_fold }
+    }
+
+    /// With `case_fold`, applies full Unicode case folding, which also maps "ß" to "ss" and
+    /// every sigma to "σ", in the canonical or compatibility caseless matching of Unicode 3.13.
+    pub fn apply(&self, string: &str) -> String {
+        if !self.case_fold {
+            return match self.form {
+                UnicodeForm::Unchanged => string.to_string(),
+                UnicodeForm::Nfc => string.nfc().collect(),
+                UnicodeForm::Nfkc => string.nfkc().collect(),
+            };
+        }
+        match self.form {
+            UnicodeForm::Unchanged => default_case_fold_str(string),
+            UnicodeForm::Nfc => string.nfd().default_case_fold().nfc().collect(),
+            UnicodeForm::Nfkc => string
+                .nfd()
+                .default_case_fold()
+                .nfkd()
+                .default_case_fold()
+                .nfkc()
+                .collect(),
+        }
+    }
+
+    fn region_name(&self, region_name: &str) -> String {
+        if *self == Self::default() {
+            return region_name.to_string();
+        }
+        let form = match self.form {
+            UnicodeForm::Unchanged => 0,
+            UnicodeForm::Nfc => 1,
+            UnicodeForm::Nfkc => 2,
+        };
+        format!(
+            "{}\0normalization:{}:{}",
+            region_name, form, self.case_fold as u8
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "E::G2Affine: Deserialize<'de>"))]
+pub struct NormalizedCiphertext<E: Engine> {
+    normalization: Normalization,
+    ciphertext: Ciphertext<E>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
+))]
+pub struct NormalizedTrapdoor<E: Engine> {
+    normalization: Normalization,
+    trapdoor: Trapdoor<E>,
+}
+
+impl<E: Engine> NormalizedCiphertext<E> {
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    pub fn ciphertext(&self) -> &Ciphertext<E> {
+        &self.ciphertext
+    }
+}
+
+impl<E: Engine> NormalizedTrapdoor<E> {
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    pub fn trapdoor(&self) -> &Trapdoor<E> {
+        &self.trapdoor
+    }
+
+    pub fn test(&self, ct: &NormalizedCiphertext<E>) -> Result<bool, ExpError<E>> {
+        if ct.normalization != self.normalization {
+            return Err(ExpError::NormalizationMismatch(
+                ct.normalization,
+                self.normalization,
+            ));
+        }
+        Ok(self.trapdoor.test(&ct.ciphertext)?)
+    }
+}
+
+pub fn gen_ciphertext_for_normalized_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    public_key: &PublicKey<E>,
+    region_name: &str,
+    string: &str,
+    normalization: Normalization,
+    rng: &mut R,
+) -> Result<NormalizedCiphertext<E>, ExpError<E>> {
+    let ciphertext = gen_ciphertext_for_prefix_search::<E, F, R>(
+        public_key,
+        &normalization.region_name(region_name),
+        &normalization.apply(string),
+        rng,
+    )?;
+    Ok(NormalizedCiphertext {
+        normalization,
+        ciphertext,
+    })
+}
+
+pub fn gen_trapdoor_for_normalized_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    prefix: &str,
+    normalization: Normalization,
+    rng: &mut R,
+) -> Result<NormalizedTrapdoor<E>, ExpError<E>> {
+    let trapdoor = gen_trapdoor_for_prefix_search::<E, F, R>(
+        secret_key,
+        &normalization.region_name(region_name),
+        &normalization.apply(prefix),
+        rng,
+    )?;
+    Ok(NormalizedTrapdoor {
+        normalization,
+        trapdoor,
+    })
+}
+
+pub fn gen_trapdoor_for_normalized_prefix_search_exact<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    string: &str,
+    normalization: Normalization,
+    rng: &mut R,
+) -> Result<NormalizedTrapdoor<E>, ExpError<E>> {
+    let trapdoor = gen_trapdoor_for_prefix_search_exact::<E, F, R>(
+        secret_key,
+        &normalization.region_name(region_name),
+        &normalization.apply(string),
+        rng,
+    )?;
+    Ok(NormalizedTrapdoor {
+        normalization,
+        trapdoor,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_normalized_prefix_search() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 15;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_normalized_prefix_search";
+        let normalization = Normalization::new(UnicodeForm::Nfc, true);
+
+        let ct = gen_ciphertext_for_normalized_prefix_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            "Alice",
+            normalization,
+            &mut rng,
+        )
+        .unwrap();
+        let trapdoor = gen_trapdoor_for_normalized_prefix_search::<_, Fr, _>(
+            &secret_key,
+            region_name,
+            "ali",
+            normalization,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(trapdoor.test(&ct).unwrap());
+
+        // "アメンボ" in NFC and in NFD.
+        let ct = gen_ciphertext_for_normalized_prefix_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            "アメンボ",
+            normalization,
+            &mut rng,
+        )
+        .unwrap();
+        let trapdoor = gen_trapdoor_for_normalized_prefix_search_exact::<_, Fr, _>(
+            &secret_key,
+            region_name,
+            "アメンホ\u{3099}",
+            normalization,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(trapdoor.test(&ct).unwrap());
+
+        let unnormalized = gen_trapdoor_for_normalized_prefix_search::<_, Fr, _>(
+            &secret_key,
+            region_name,
+            "アメンボ",
+            Normalization::default(),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(matches!(
+            unnormalized.test(&ct),
+            Err(ExpError::NormalizationMismatch(_, _))
+        ));
+        assert!(!unnormalized.trapdoor().test(ct.ciphertext()).unwrap());
+    }
+
+    #[test]
+    fn test_normalization_full_case_folding() {
+        for form in [UnicodeForm::Unchanged, UnicodeForm::Nfc, UnicodeForm::Nfkc] {
+            let normalization = Normalization::new(form, true);
+            assert_eq!(
+                normalization.apply("Straße"),
+                normalization.apply("STRASSE")
+            );
+            assert_eq!(normalization.apply("Straße"), "strasse");
+            // Final and non-final small sigma fold to the same letter as the capital one.
+            assert_eq!(
+                normalization.apply("ΟΔΥΣΣΕΥΣ"),
+                normalization.apply("οδυσσευς")
+            );
+            assert_eq!(normalization.apply("οδυσσευς"), "οδυσσευσ");
+        }
+        let nfkc = Normalization::new(UnicodeForm::Nfkc, true);
+        assert_eq!(nfkc.apply("ＡＢＣ"), "abc");
+        assert_ne!(
+            Normalization::new(UnicodeForm::Nfc, false).apply("Straße"),
+            Normalization::new(UnicodeForm::Nfc, false).apply("STRASSE")
+        );
+    }
+}
@@ -17,26 +17,9 @@ pub fn gen_ciphertext_for_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
     if n_bytes > max_bytes {
         return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
     }
-    let n_remaining = max_bytes - n_bytes;
 
-    let mut keywords = bytes
-        .into_iter()
-        .enumerate()
-        .map(|(idx, byte)| {
-            concat_multi_bytes(vec![
-                region_name.as_bytes(),
-                &idx.to_be_bytes(),
-                &vec![1u8, *byte],
-            ])
-        })
-        .collect::<Vec<Vec<u8>>>();
-    for idx in 0..n_remaining {
-        keywords.push(concat_multi_bytes(vec![
-            region_name.as_bytes(),
-            &idx.to_be_bytes(),
-            &vec![0u8, 0u8],
-        ]));
-    }
+    let mut keywords = prefix_keywords(region_name, bytes);
+    keywords.append(&mut length_keywords(region_name, max_bytes - n_bytes));
     let ct = public_key.encrypt::<R, F>(keywords, rng)?;
     Ok(ct)
 }
@@ -54,17 +37,7 @@ pub fn gen_trapdoor_for_prefix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
         return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
     }
 
-    let keywords = bytes
-        .into_iter()
-        .enumerate()
-        .map(|(idx, byte)| {
-            concat_multi_bytes(vec![
-                region_name.as_bytes(),
-                &idx.to_be_bytes(),
-                &vec![1u8, *byte],
-            ])
-        })
-        .collect::<Vec<Vec<u8>>>();
+    let keywords = prefix_keywords(region_name, bytes);
     let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
     Ok(td)
 }
@@ -81,28 +54,40 @@ pub fn gen_trapdoor_for_prefix_search_exact<E: Engine, F: BaseROFr<E>, R: RngCor
     if n_bytes > max_bytes {
         return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
     }
-    let n_remaining = max_bytes - n_bytes;
 
-    let mut keywords = bytes
-        .into_iter()
+    let mut keywords = prefix_keywords(region_name, bytes);
+    keywords.append(&mut length_keywords(region_name, max_bytes - n_bytes));
+    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
+    Ok(td)
+}
+
+fn prefix_keywords(region_name: &str, bytes: &[u8]) -> Vec<Vec<u8>> {
+    bytes
+        .iter()
         .enumerate()
         .map(|(idx, byte)| {
             concat_multi_bytes(vec![
                 region_name.as_bytes(),
                 &idx.to_be_bytes(),
-                &vec![1u8, *byte],
+                &[1u8, *byte],
             ])
         })
-        .collect::<Vec<Vec<u8>>>();
-    for idx in 0..n_remaining {
-        keywords.push(concat_multi_bytes(vec![
-            region_name.as_bytes(),
-            &idx.to_be_bytes(),
-            &vec![0u8, 0u8],
-        ]));
-    }
-    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
-    Ok(td)
+        .collect()
+}
+
+/// Encodes the string length as `n_remaining` fixed keywords at indices `0..n_remaining`,
+/// so that an exact trapdoor only matches ciphertexts with the same number of unused slots.
+/// This is the layout of ciphertexts stored before `encrypt` padded them internally.
+fn length_keywords(region_name: &str, n_remaining: usize) -> Vec<Vec<u8>> {
+    (0..n_remaining)
+        .map(|idx| {
+            concat_multi_bytes(vec![
+                region_name.as_bytes(),
+                &idx.to_be_bytes(),
+                &[0u8, 0u8],
+            ])
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -270,4 +255,75 @@ mod test {
         .unwrap();
         assert_eq!(trapdoor.test(&ct).unwrap(), false);
     }
+
+    #[test]
+    fn test_prefix_case_exact_shorter_string() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 5;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_prefix_case_exact_shorter_string";
+        let ct =
+            gen_ciphertext_for_prefix_search::<_, Fr, _>(&public_key, region_name, "abc", &mut rng)
+                .unwrap();
+        for (string, expected) in [("abc", true), ("ab", false), ("abcd", false)] {
+            let trapdoor = gen_trapdoor_for_prefix_search_exact::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                string,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+        let trapdoor =
+            gen_trapdoor_for_prefix_search::<_, Fr, _>(&secret_key, region_name, "ab", &mut rng)
+                .unwrap();
+        assert!(trapdoor.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_prefix_case_exact_legacy_layout() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 5;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_prefix_case_exact_legacy_layout";
+        // The keyword layout written by earlier releases, with every slot filled.
+        let mut keywords = b"abc"
+            .iter()
+            .enumerate()
+            .map(|(idx, byte)| {
+                concat_multi_bytes(vec![
+                    region_name.as_bytes(),
+                    &idx.to_be_bytes(),
+                    &[1u8, *byte],
+                ])
+            })
+            .collect::<Vec<Vec<u8>>>();
+        for idx in 0..(n - 3) {
+            keywords.push(concat_multi_bytes(vec![
+                region_name.as_bytes(),
+                &idx.to_be_bytes(),
+                &[0u8, 0u8],
+            ]));
+        }
+        let ct = public_key.encrypt::<_, Fr>(keywords, &mut rng).unwrap();
+        for (string, expected) in [("abc", true), ("ab", false), ("abcd", false)] {
+            let trapdoor = gen_trapdoor_for_prefix_search_exact::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                string,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+    }
 }
@@ -8,6 +8,8 @@ use multiset::HashMultiSet;
 use paired::Engine;
 use rand_core::RngCore;
 
+/// Returns the number of keywords a `SecretKey` needs for range search over `bit_size`-bit values,
+/// since a range trapdoor may hold up to two canonical cover nodes per bit.
 pub fn compute_max_keyword_size(bit_size: usize) -> usize {
     2 * bit_size
 }
@@ -19,7 +21,6 @@ pub fn gen_ciphertext_for_range_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
     val: u64,
     rng: &mut R,
 ) -> Result<Ciphertext<E>, ExpError<E>> {
-    let max_keyword_size = compute_max_keyword_size(bit_size);
     let bits = uint2bits(val, bit_size);
     let sliced_uints = (0..bit_size)
         .map(|i| {
@@ -27,7 +28,7 @@ pub fn gen_ciphertext_for_range_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
             (i, bits2uint(sliced_bits))
         })
         .collect::<Vec<(usize, u64)>>();
-    let mut keywords: Vec<Vec<u8>> = sliced_uints
+    let keywords: Vec<Vec<u8>> = sliced_uints
         .into_iter()
         .map(|(i, val)| {
             concat_multi_bytes(vec![
@@ -38,14 +39,6 @@ pub fn gen_ciphertext_for_range_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
             ])
         })
         .collect();
-    for _ in 0..(max_keyword_size - keywords.len()) {
-        keywords.push(concat_multi_bytes(vec![
-            region_name.as_bytes(),
-            &vec![0u8],
-            &0u64.to_be_bytes(),
-            &0u64.to_be_bytes(),
-        ]));
-    }
     let ct = public_key.encrypt::<R, F>(keywords, rng)?;
     Ok(ct)
 }
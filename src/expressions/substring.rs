@@ -0,0 +1,217 @@
+//! Substring and suffix search.
+//!
+//! A substring ciphertext encrypts the distinct `NGRAM_SIZE`-byte n-grams of the string
+//! enclosed by a `0xff` boundary byte on both ends, which never occurs in UTF-8.
+//! A trapdoor for a substring is the AND of its n-grams, so a string containing every
+//! n-gram of the substring at unrelated positions also matches.
+//!
+//! Keyword budget: a string of `L` bytes takes at most `max(L, 1)` keywords,
+//! and a substring of `l >= NGRAM_SIZE` bytes takes `l - NGRAM_SIZE + 1` keywords.
+//!
+//! The suffix-only helpers index each byte by its distance from the end of the string,
+//! like the prefix search, and have no false positives.
+//! A string of `L` bytes takes `L` keywords and a suffix of `l` bytes takes `l` keywords.
+
+use super::utils::*;
+use super::ExpError;
+use crate::pecdk::*;
+use crate::BaseROFr;
+use paired::Engine;
+use rand_core::RngCore;
+
+pub const NGRAM_SIZE: usize = 3;
+const BOUNDARY: u8 = 0xff;
+
+pub fn gen_ciphertext_for_substring_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    public_key: &PublicKey<E>,
+    region_name: &str,
+    string: &str,
+    rng: &mut R,
+) -> Result<Ciphertext<E>, ExpError<E>> {
+    let mut bounded = vec![BOUNDARY];
+    bounded.extend_from_slice(string.as_bytes());
+    bounded.push(BOUNDARY);
+    let keywords = ngram_keywords(region_name, &bounded);
+    let max_keywords = public_key.num_keyword();
+    if keywords.len() > max_keywords {
+        return Err(ExpError::ExcessiveNumberOfKeywords(
+            keywords.len(),
+            max_keywords,
+        ));
+    }
+    let ct = public_key.encrypt::<R, F>(keywords, rng)?;
+    Ok(ct)
+}
+
+pub fn gen_trapdoor_for_substring_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    substring: &str,
+    rng: &mut R,
+) -> Result<Trapdoor<E>, ExpError<E>> {
+    let bytes = substring.as_bytes();
+    if bytes.len() < NGRAM_SIZE {
+        return Err(ExpError::QueryTooShort(bytes.len(), NGRAM_SIZE));
+    }
+    let keywords = ngram_keywords(region_name, bytes);
+    let max_keywords = secret_key.num_keyword();
+    if keywords.len() > max_keywords {
+        return Err(ExpError::ExcessiveNumberOfKeywords(
+            keywords.len(),
+            max_keywords,
+        ));
+    }
+    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
+    Ok(td)
+}
+
+pub fn gen_ciphertext_for_suffix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    public_key: &PublicKey<E>,
+    region_name: &str,
+    string: &str,
+    rng: &mut R,
+) -> Result<Ciphertext<E>, ExpError<E>> {
+    let bytes = string.as_bytes();
+    let n_bytes = bytes.len();
+    let max_bytes = public_key.num_keyword();
+    if n_bytes > max_bytes {
+        return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
+    }
+    let ct = public_key.encrypt::<R, F>(suffix_keywords(region_name, bytes), rng)?;
+    Ok(ct)
+}
+
+pub fn gen_trapdoor_for_suffix_search<E: Engine, F: BaseROFr<E>, R: RngCore>(
+    secret_key: &SecretKey<E>,
+    region_name: &str,
+    suffix: &str,
+    rng: &mut R,
+) -> Result<Trapdoor<E>, ExpError<E>> {
+    let bytes = suffix.as_bytes();
+    let n_bytes = bytes.len();
+    let max_bytes = secret_key.num_keyword();
+    if n_bytes > max_bytes {
+        return Err(ExpError::ExcessiveNumberOfKeywords(n_bytes, max_bytes));
+    }
+    let keywords = suffix_keywords(region_name, bytes);
+    let td = secret_key.gen_trapdoor::<R, F>(keywords, SearchSym::AND, rng)?;
+    Ok(td)
+}
+
+/// The distinct n-grams of `bytes`, or `bytes` itself if it is shorter than an n-gram.
+fn ngram_keywords(region_name: &str, bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut keywords = bytes
+        .windows(NGRAM_SIZE.min(bytes.len()))
+        .map(|gram| concat_multi_bytes(vec![region_name.as_bytes(), &[3u8], gram]))
+        .collect::<Vec<Vec<u8>>>();
+    // A repeated n-gram would count once in the ciphertext but twice in the AND trapdoor.
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+fn suffix_keywords(region_name: &str, bytes: &[u8]) -> Vec<Vec<u8>> {
+    bytes
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, byte)| {
+            concat_multi_bytes(vec![
+                region_name.as_bytes(),
+                &idx.to_be_bytes(),
+                &[2u8, *byte],
+            ])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_substring_search() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 8;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_substring_search";
+        let ct = gen_ciphertext_for_substring_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            "id-4821x",
+            &mut rng,
+        )
+        .unwrap();
+        for (substring, expected) in [
+            ("482", true),
+            ("d-48", true),
+            ("id-4821x", true),
+            ("4822", false),
+        ] {
+            let trapdoor = gen_trapdoor_for_substring_search::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                substring,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+
+        assert!(matches!(
+            gen_trapdoor_for_substring_search::<_, Fr, _>(&secret_key, region_name, "48", &mut rng),
+            Err(ExpError::QueryTooShort(2, NGRAM_SIZE))
+        ));
+        assert!(matches!(
+            gen_ciphertext_for_substring_search::<_, Fr, _>(
+                &public_key,
+                region_name,
+                "too long string",
+                &mut rng
+            ),
+            Err(ExpError::ExcessiveNumberOfKeywords(15, 8))
+        ));
+    }
+
+    #[test]
+    fn test_suffix_search() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 8;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let region_name = "test_suffix_search";
+        let ct = gen_ciphertext_for_suffix_search::<_, Fr, _>(
+            &public_key,
+            region_name,
+            "main.rs",
+            &mut rng,
+        )
+        .unwrap();
+        for (suffix, expected) in [
+            (".rs", true),
+            ("main.rs", true),
+            (".r", false),
+            ("x.rs", false),
+        ] {
+            let trapdoor = gen_trapdoor_for_suffix_search::<_, Fr, _>(
+                &secret_key,
+                region_name,
+                suffix,
+                &mut rng,
+            )
+            .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use groupy::CurveProjective;
 use paired::{hash_to_field, Compress, Engine, ExpandMsgXmd};
 use sha2::Sha256;
 use std::io;
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -29,7 +30,63 @@ pub(crate) fn hash_bytes2point<E: Engine>(bytes: &[u8]) -> E::G1 {
 }
 
 pub(crate) fn hash_field2bytes<E: Engine>(field: E::Fqk) -> Result<Vec<u8>, ECHashError> {
-    let mut field_bytes = Vec::new();
+    hash_labeled_field2bytes::<E>(&[], field)
+}
+
+/// Hashes `label` followed by the compressed `field`, so an empty label gives `hash_field2bytes`.
+pub(crate) fn hash_labeled_field2bytes<E: Engine>(
+    label: &[u8],
+    field: E::Fqk,
+) -> Result<Vec<u8>, ECHashError> {
+    let mut field_bytes = label.to_vec();
     field.write_compressed(&mut field_bytes)?;
     Ok(<Sha256 as Digest>::digest(&field_bytes).to_vec())
 }
+
+/// Compares digests without branching on their contents.
+/// Only a length mismatch, which is public, is decided early.
+pub(crate) fn digest_eq<T: ConstantTimeEq>(left: &[T], right: &[T]) -> bool {
+    left.ct_eq(right).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use subtle::Choice;
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct CountingByte(u8);
+
+    impl ConstantTimeEq for CountingByte {
+        fn ct_eq(&self, other: &Self) -> Choice {
+            COMPARISONS.with(|count| count.set(count.get() + 1));
+            self.0.ct_eq(&other.0)
+        }
+    }
+
+    fn count_comparisons(left: &[u8], right: &[u8]) -> (bool, usize) {
+        let left = left.iter().map(|b| CountingByte(*b)).collect::<Vec<_>>();
+        let right = right.iter().map(|b| CountingByte(*b)).collect::<Vec<_>>();
+        COMPARISONS.with(|count| count.set(0));
+        let is_eq = digest_eq(&left, &right);
+        (is_eq, COMPARISONS.with(|count| count.get()))
+    }
+
+    #[test]
+    fn test_digest_eq_does_not_short_circuit() {
+        let digest = [0xabu8; 32];
+        let mut first_differs = digest;
+        first_differs[0] ^= 1;
+        let mut last_differs = digest;
+        last_differs[31] ^= 1;
+
+        assert_eq!(count_comparisons(&digest, &digest), (true, 32));
+        assert_eq!(count_comparisons(&digest, &first_differs), (false, 32));
+        assert_eq!(count_comparisons(&digest, &last_differs), (false, 32));
+        assert!(!digest_eq(&digest[..], &digest[..31]));
+    }
+}
@@ -1,12 +1,24 @@
+#[cfg(feature = "bn254")]
+pub mod bn254;
+pub mod curve;
+pub mod dpeks;
 pub mod expressions;
 mod hashes;
+pub mod paeks;
+mod payload;
 pub mod pecdk;
 pub mod peks;
+pub mod seed;
 mod utils;
+mod wipe;
+mod wire;
 
 #[cfg(feature = "c_api")]
 mod c_utils;
 
+pub use payload::PayloadError;
+pub use wire::WireError;
+
 use paired::{
     bls12_381::{Bls12, Fr},
     BaseFromRO, Engine,
@@ -15,3 +27,12 @@ use paired::{
 pub trait BaseROFr<E: Engine>: BaseFromRO + Clone + From<E::Fr> + Into<E::Fr> {}
 
 impl BaseROFr<Bls12> for Fr {}
+
+/// Identifies the pairing curve of an engine in serialized objects.
+pub trait EngineId: Engine {
+    const CURVE_ID: u8;
+}
+
+impl EngineId for Bls12 {
+    const CURVE_ID: u8 = 1;
+}
@@ -0,0 +1,125 @@
+use crate::c_utils::*;
+use crate::curve::CurveEngine;
+use crate::paeks::*;
+use rand_core::OsRng;
+use std::os::raw::c_char;
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CPaeksSecretKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CPaeksPublicKey {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CPaeksCiphertext {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct CPaeksTrapdoor {
+    pub(crate) ptr: *mut c_char,
+}
+
+#[no_mangle]
+pub extern "C" fn paeksGenSecretKey(secret_key: *mut CPaeksSecretKey) -> CErrorCode {
+    ffi_output("paeksGenSecretKey", secret_key, || {
+        let mut rng = OsRng;
+        let sk = SecretKey::<CurveEngine>::gen(&mut rng);
+        Ok(CPaeksSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn paeksGenPublicKey(
+    secret_key: CPaeksSecretKey,
+    public_key: *mut CPaeksPublicKey,
+) -> CErrorCode {
+    ffi_output("paeksGenPublicKey", public_key, || {
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let pk = sk.into_public_key();
+        Ok(CPaeksPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn paeksEncryptKeyword(
+    sender_secret_key: CPaeksSecretKey,
+    receiver_public_key: CPaeksPublicKey,
+    keyword: *mut c_char,
+    ciphertext: *mut CPaeksCiphertext,
+) -> CErrorCode {
+    ffi_output("paeksEncryptKeyword", ciphertext, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(sender_secret_key.ptr)?;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(receiver_public_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let ct = sk.encrypt(&pk, keyword, &mut rng).map_err(CError::crypto)?;
+        Ok(CPaeksCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn paeksGenTrapdoor(
+    receiver_secret_key: CPaeksSecretKey,
+    sender_public_key: CPaeksPublicKey,
+    keyword: *mut c_char,
+    trapdoor: *mut CPaeksTrapdoor,
+) -> CErrorCode {
+    ffi_output("paeksGenTrapdoor", trapdoor, || {
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(receiver_secret_key.ptr)?;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(sender_public_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let td = sk.gen_trapdoor(&pk, keyword);
+        Ok(CPaeksTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn paeksTest(
+    ciphertext: CPaeksCiphertext,
+    trapdoor: CPaeksTrapdoor,
+    result: *mut bool,
+) -> CErrorCode {
+    ffi_output("paeksTest", result, || {
+        let ct = from_json_ptr::<Ciphertext<CurveEngine>>(ciphertext.ptr)?;
+        let td = from_json_ptr::<Trapdoor<CurveEngine>>(trapdoor.ptr)?;
+        td.test(&ct).map_err(CError::crypto)
+    })
+}
+
+/// Overwrites the serialized secret key with zeros before freeing it.
+#[no_mangle]
+pub extern "C" fn paeksFreeSecretKey(secret_key: CPaeksSecretKey) {
+    drop_secret_ptr(secret_key.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn paeksFreePublicKey(public_key: CPaeksPublicKey) {
+    drop_ptr(public_key.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn paeksFreeCiphertext(ciphertext: CPaeksCiphertext) {
+    drop_ptr(ciphertext.ptr);
+}
+
+#[no_mangle]
+pub extern "C" fn paeksFreeTrapdoor(trapdoor: CPaeksTrapdoor) {
+    drop_ptr(trapdoor.ptr);
+}
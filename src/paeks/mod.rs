@@ -0,0 +1,178 @@
+//! Public-key authenticated encryption with keyword search (PAEKS).
+//! Keywords are hashed together with the Diffie-Hellman key of the sender and the receiver,
+//! so a trapdoor only matches ciphertexts from the sender named when generating it,
+//! and a server without either secret key cannot encrypt guessed keywords.
+//! This module searches a single keyword; `pecdk::PublicKey::encrypt_authenticated` uses the same
+//! key pairs to authenticate multi-keyword PECDK ciphertexts and AND/OR trapdoors.
+
+#[cfg(feature = "c_api")]
+mod c_api;
+
+use crate::hashes::*;
+use crate::wipe::wipe;
+
+#[cfg(feature = "c_api")]
+pub use c_api::*;
+
+use fff::Field;
+use groupy::{CurveAffine, CurveProjective};
+use paired::Engine;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PAEKSError {
+    #[error(transparent)]
+    ECHashError(#[from] ECHashError),
+}
+
+/// A key pair is used both as a sender and as a receiver.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretKey<E: Engine> {
+    alpha: E::Fr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKey<E: Engine> {
+    y: E::G2Affine,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ciphertext<E: Engine> {
+    a: E::G2Affine,
+    b: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trapdoor<E: Engine> {
+    t: E::G1Affine,
+}
+
+impl<E: Engine> SecretKey<E> {
+    pub fn gen<R: RngCore>(rng: &mut R) -> Self {
+        let alpha = <E::Fr as Field>::random(rng);
+        Self { alpha }
+    }
+
+    pub fn into_public_key(&self) -> PublicKey<E> {
+        let y = E::G2Affine::one().mul(self.alpha).into_affine();
+        PublicKey { y }
+    }
+
+    /// Encrypts `keyword` as a ciphertext from this sender to `receiver`.
+    pub fn encrypt<R: RngCore>(
+        &self,
+        receiver: &PublicKey<E>,
+        keyword: &[u8],
+        rng: &mut R,
+    ) -> Result<Ciphertext<E>, PAEKSError> {
+        let hashed_w = self.hash_keyword(receiver, keyword);
+        let r = <E::Fr as Field>::random(rng);
+        let a = E::G2Affine::one().mul(r).into_affine();
+        let pairinged = E::pairing(hashed_w, receiver.y.mul(r));
+        let b = hash_field2bytes::<E>(pairinged)?;
+        Ok(Ciphertext { a, b })
+    }
+
+    /// Generates a trapdoor matching only ciphertexts of `keyword` from `sender`.
+    pub fn gen_trapdoor(&self, sender: &PublicKey<E>, keyword: &[u8]) -> Trapdoor<E> {
+        let hashed_w = self.hash_keyword(sender, keyword).into_affine();
+        let t = hashed_w.mul(self.alpha).into_affine();
+        Trapdoor { t }
+    }
+
+    fn hash_keyword(&self, other: &PublicKey<E>, keyword: &[u8]) -> E::G1 {
+        let mut bytes = self.shared_key(other);
+        bytes.extend_from_slice(keyword);
+        hash_bytes2point::<E>(&bytes)
+    }
+
+    /// The Diffie-Hellman key of this key pair and `other`, which both sides can compute.
+    pub(crate) fn shared_key(&self, other: &PublicKey<E>) -> Vec<u8> {
+        let shared = other.y.mul(self.alpha).into_affine().into_compressed();
+        shared.as_ref().to_vec()
+    }
+}
+
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        wipe(
+            std::slice::from_mut(&mut self.alpha),
+            <E::Fr as Field>::zero(),
+        );
+    }
+}
+
+impl<E: Engine> PublicKey<E> {
+    pub fn from_secret_key(secret_key: &SecretKey<E>) -> Self {
+        secret_key.into_public_key()
+    }
+}
+
+impl<E: Engine> Trapdoor<E> {
+    pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PAEKSError> {
+        let pairinged = E::pairing(self.t.into_projective(), ct.a.into_projective());
+        let hashed = hash_field2bytes::<E>(pairinged)?;
+        Ok(digest_eq(&hashed, &ct.b))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::Bls12;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_paeks_sender_authentication() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let sender_sk = SecretKey::<Bls12>::gen(&mut rng);
+        let sender_pk = sender_sk.into_public_key();
+        let receiver_sk = SecretKey::<Bls12>::gen(&mut rng);
+        let receiver_pk = receiver_sk.into_public_key();
+        let forger_sk = SecretKey::<Bls12>::gen(&mut rng);
+
+        let ct = sender_sk
+            .encrypt(&receiver_pk, b"keyword1", &mut rng)
+            .unwrap();
+        let trapdoor = receiver_sk.gen_trapdoor(&sender_pk, b"keyword1");
+        assert!(trapdoor.test(&ct).unwrap());
+
+        let other_keyword = sender_sk
+            .encrypt(&receiver_pk, b"keyword2", &mut rng)
+            .unwrap();
+        assert!(!trapdoor.test(&other_keyword).unwrap());
+        let forged = forger_sk
+            .encrypt(&receiver_pk, b"keyword1", &mut rng)
+            .unwrap();
+        assert!(!trapdoor.test(&forged).unwrap());
+
+        let json = serde_json::to_string(&trapdoor).unwrap();
+        let decoded: Trapdoor<Bls12> = serde_json::from_str(&json).unwrap();
+        assert!(decoded.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_paeks_secret_key_debug_redacted() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng);
+        let debug = format!("{:?}", secret_key);
+        assert_eq!(debug, "SecretKey { .. }");
+        assert!(!debug.contains(&secret_key.alpha.to_string()));
+    }
+}
@@ -0,0 +1,74 @@
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+const NONCE_SIZE: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum PayloadError {
+    #[error("The nonce length is {0}, but {} is expected", NONCE_SIZE)]
+    InvalidNonceLength(usize),
+    #[error("The payload could not be encrypted")]
+    EncryptionFailure,
+    #[error("The payload could not be decrypted or authenticated")]
+    DecryptionFailure,
+}
+
+/// AEAD-encrypted payload whose key is derived from a shared secret with HKDF-SHA256.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SealedPayload {
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+fn derive_cipher(shared: &[u8], info: &[u8]) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared)
+        .expand(info, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    ChaCha20Poly1305::new(&Key::from(key))
+}
+
+impl SealedPayload {
+    pub(crate) fn seal<R: RngCore>(
+        shared: &[u8],
+        info: &[u8],
+        aad: &[u8],
+        msg: &[u8],
+        rng: &mut R,
+    ) -> Result<Self, PayloadError> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+        let ciphertext = derive_cipher(shared, info)
+            .encrypt(&Nonce::from(nonce), Payload { msg, aad })
+            .map_err(|_| PayloadError::EncryptionFailure)?;
+        Ok(Self {
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    pub(crate) fn open(
+        &self,
+        shared: &[u8],
+        info: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, PayloadError> {
+        let nonce = <[u8; NONCE_SIZE]>::try_from(self.nonce.as_slice())
+            .map_err(|_| PayloadError::InvalidNonceLength(self.nonce.len()))?;
+        derive_cipher(shared, info)
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| PayloadError::DecryptionFailure)
+    }
+}
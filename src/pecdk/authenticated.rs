@@ -0,0 +1,164 @@
+use super::*;
+use crate::paeks;
+
+const AUTHENTICATED_TAG: &str = "pecdk_authenticated_hash_to_field";
+
+/// Hashes each keyword together with the Diffie-Hellman key of the sender and the receiver,
+/// so that a server holding only public keys cannot encrypt guessed keywords.
+fn hash_authenticated_keywords<E: Engine, F: BaseROFr<E>>(
+    shared_key: &[u8],
+    keywords: Vec<Vec<u8>>,
+) -> Result<Vec<E::Fr>, ECHashError> {
+    keywords
+        .into_par_iter()
+        .map(|word| {
+            let mut bytes = shared_key.to_vec();
+            bytes.extend_from_slice(&word);
+            hash_bytes2field::<F, E>(&bytes, AUTHENTICATED_TAG.as_bytes())
+        })
+        .collect()
+}
+
+impl<E: Engine> PublicKey<E> {
+    /// Encrypts `keywords` as a ciphertext from the holder of `sender` to the holder of `receiver`.
+    /// It only matches trapdoors of `SecretKey::gen_authenticated_trapdoor` naming the same sender.
+    pub fn encrypt_authenticated<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        sender: &paeks::SecretKey<E>,
+        receiver: &paeks::PublicKey<E>,
+        keywords: Vec<Vec<u8>>,
+        rng: &mut R,
+    ) -> Result<Ciphertext<E>, PECDKError<E>> {
+        let n = self.num_keyword();
+        if keywords.len() > n {
+            return Err(PECDKError::ExcessiveNumberOfKeywords(keywords.len(), n));
+        }
+        let shared_key = sender.shared_key(receiver);
+        let hashed_words = hash_authenticated_keywords::<E, F>(&shared_key, keywords)?;
+        self.encrypt_hashed(hashed_words, &[], rng)
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    /// Generates a trapdoor matching only ciphertexts of `PublicKey::encrypt_authenticated`
+    /// from the holder of `sender`, where `receiver` is the PAEKS key of this key's owner.
+    pub fn gen_authenticated_trapdoor<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        receiver: &paeks::SecretKey<E>,
+        sender: &paeks::PublicKey<E>,
+        keywords: Vec<Vec<u8>>,
+        sym: SearchSym,
+        rng: &mut R,
+    ) -> Result<Trapdoor<E>, PECDKError<E>> {
+        let shared_key = receiver.shared_key(sender);
+        let hashes = hash_authenticated_keywords::<E, F>(&shared_key, keywords)?;
+        self.gen_trapdoor_hashed(hashes, sym, rng)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_authenticated_and_or() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 3;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let receiver_sk = paeks::SecretKey::<Bls12>::gen(&mut rng);
+        let receiver_pk = receiver_sk.into_public_key();
+        let sender_sk = paeks::SecretKey::<Bls12>::gen(&mut rng);
+        let sender_pk = sender_sk.into_public_key();
+        let forger_sk = paeks::SecretKey::<Bls12>::gen(&mut rng);
+
+        let keywords = vec![b"A".to_vec(), b"B".to_vec(), b"C".to_vec()];
+        let ct = public_key
+            .encrypt_authenticated::<_, Fr>(&sender_sk, &receiver_pk, keywords.clone(), &mut rng)
+            .unwrap();
+        let and_td = secret_key
+            .gen_authenticated_trapdoor::<_, Fr>(
+                &receiver_sk,
+                &sender_pk,
+                vec![b"A".to_vec(), b"C".to_vec()],
+                SearchSym::AND,
+                &mut rng,
+            )
+            .unwrap();
+        let or_td = secret_key
+            .gen_authenticated_trapdoor::<_, Fr>(
+                &receiver_sk,
+                &sender_pk,
+                vec![b"D".to_vec(), b"B".to_vec()],
+                SearchSym::OR,
+                &mut rng,
+            )
+            .unwrap();
+        let missing_td = secret_key
+            .gen_authenticated_trapdoor::<_, Fr>(
+                &receiver_sk,
+                &sender_pk,
+                vec![b"A".to_vec(), b"D".to_vec()],
+                SearchSym::AND,
+                &mut rng,
+            )
+            .unwrap();
+        assert!(and_td.test(&ct).unwrap());
+        assert!(or_td.test(&ct).unwrap());
+        assert!(!missing_td.test(&ct).unwrap());
+
+        // Another sender, or anyone with only the PECDK public key, cannot produce a match.
+        let forged = public_key
+            .encrypt_authenticated::<_, Fr>(&forger_sk, &receiver_pk, keywords.clone(), &mut rng)
+            .unwrap();
+        assert!(!and_td.test(&forged).unwrap());
+        assert!(!or_td.test(&forged).unwrap());
+        let unauthenticated = public_key.encrypt::<_, Fr>(keywords, &mut rng).unwrap();
+        assert!(!and_td.test(&unauthenticated).unwrap());
+        assert!(!or_td.test(&unauthenticated).unwrap());
+
+        let plain_td = secret_key
+            .gen_trapdoor::<_, Fr>(vec![b"A".to_vec()], SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(!plain_td.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_authenticated_excessive_keywords() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 1;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let receiver_sk = paeks::SecretKey::<Bls12>::gen(&mut rng);
+        let sender_sk = paeks::SecretKey::<Bls12>::gen(&mut rng);
+        let keywords = vec![b"A".to_vec(), b"B".to_vec()];
+        assert!(matches!(
+            public_key.encrypt_authenticated::<_, Fr>(
+                &sender_sk,
+                &receiver_sk.into_public_key(),
+                keywords.clone(),
+                &mut rng,
+            ),
+            Err(PECDKError::ExcessiveNumberOfKeywords(2, 1))
+        ));
+        assert!(matches!(
+            secret_key.gen_authenticated_trapdoor::<_, Fr>(
+                &receiver_sk,
+                &sender_sk.into_public_key(),
+                keywords,
+                SearchSym::OR,
+                &mut rng,
+            ),
+            Err(PECDKError::ExcessiveNumberOfKeywords(2, 1))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use super::*;
+use std::borrow::{Borrow, Cow};
+use std::collections::VecDeque;
+
+impl<E: Engine> Trapdoor<E> {
+    /// Tests every ciphertext, preparing the trapdoor only once.
+    pub fn test_batch(&self, cts: &[Ciphertext<E>]) -> Result<Vec<bool>, PECDKError<E>> {
+        self.prepare().test_batch(cts)
+    }
+
+    /// Lazily tests the ciphertexts yielded by `cts` in the order they are given.
+    pub fn test_stream<C, I>(&self, cts: I) -> TestStream<'static, E, I::IntoIter>
+    where
+        C: Borrow<Ciphertext<E>> + Send,
+        I: IntoIterator<Item = C>,
+    {
+        TestStream::new(Cow::Owned(self.prepare()), cts.into_iter())
+    }
+}
+
+impl<E: Engine> PreparedTrapdoor<E> {
+    pub fn test_batch(&self, cts: &[Ciphertext<E>]) -> Result<Vec<bool>, PECDKError<E>> {
+        cts.par_iter().map(|ct| self.test(ct)).collect()
+    }
+
+    pub fn test_stream<C, I>(&self, cts: I) -> TestStream<'_, E, I::IntoIter>
+    where
+        C: Borrow<Ciphertext<E>> + Send,
+        I: IntoIterator<Item = C>,
+    {
+        TestStream::new(Cow::Borrowed(self), cts.into_iter())
+    }
+}
+
+/// Iterator returned by `test_stream`.
+/// Ciphertexts are pulled and tested in parallel chunks.
+pub struct TestStream<'a, E: Engine, I> {
+    prepared: Cow<'a, PreparedTrapdoor<E>>,
+    cts: I,
+    results: VecDeque<Result<bool, PECDKError<E>>>,
+    chunk_size: usize,
+}
+
+impl<'a, E: Engine, I> TestStream<'a, E, I> {
+    fn new(prepared: Cow<'a, PreparedTrapdoor<E>>, cts: I) -> Self {
+        Self {
+            prepared,
+            cts,
+            results: VecDeque::new(),
+            chunk_size: rayon::current_num_threads(),
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+}
+
+impl<'a, E, C, I> Iterator for TestStream<'a, E, I>
+where
+    E: Engine,
+    C: Borrow<Ciphertext<E>> + Send,
+    I: Iterator<Item = C>,
+{
+    type Item = Result<bool, PECDKError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.results.is_empty() {
+            let chunk = self.cts.by_ref().take(self.chunk_size).collect::<Vec<C>>();
+            let prepared = self.prepared.as_ref();
+            let results = chunk
+                .into_par_iter()
+                .map(|ct| prepared.test(ct.borrow()))
+                .collect::<Vec<_>>();
+            self.results.extend(results);
+        }
+        self.results.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_pecdk_batch() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let cts = (0..6u8)
+            .map(|i| {
+                public_key
+                    .encrypt::<_, Fr>(vec![vec![i % 2], vec![i, i]], &mut rng)
+                    .unwrap()
+            })
+            .collect::<Vec<Ciphertext<Bls12>>>();
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(vec![vec![1u8]], SearchSym::AND, &mut rng)
+            .unwrap();
+        let expected = vec![false, true, false, true, false, true];
+
+        assert_eq!(trapdoor.test_batch(&cts).unwrap(), expected);
+        let streamed = trapdoor
+            .test_stream(cts.iter())
+            .with_chunk_size(4)
+            .collect::<Result<Vec<bool>, _>>()
+            .unwrap();
+        assert_eq!(streamed, expected);
+        let prepared = trapdoor.prepare();
+        assert_eq!(prepared.test_batch(&cts).unwrap(), expected);
+        let streamed = prepared
+            .test_stream(cts)
+            .collect::<Result<Vec<bool>, _>>()
+            .unwrap();
+        assert_eq!(streamed, expected);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::c_utils::*;
+use crate::curve::{CurveEngine, CurveFr};
+use crate::paeks::{self, CPaeksPublicKey, CPaeksSecretKey};
 use crate::pecdk::*;
-use core::slice;
-use errno::{set_errno, Errno};
-use paired::bls12_381::{Bls12, Fr};
+use crate::seed::CSeed;
 use rand_core::OsRng;
 use std::os::raw::c_char;
 use std::os::raw::c_int;
@@ -31,67 +31,119 @@ pub struct CPecdkTrapdoor {
     pub(crate) ptr: *mut c_char,
 }
 
-pub(crate) const EINVAL: i32 = 22;
+#[no_mangle]
+pub extern "C" fn pecdkGenSecretKey(
+    num_keyword: usize,
+    secret_key: *mut CPecdkSecretKey,
+) -> CErrorCode {
+    ffi_output("pecdkGenSecretKey", secret_key, || {
+        let mut rng = OsRng;
+        let sk = SecretKey::<CurveEngine>::gen(&mut rng, num_keyword);
+        Ok(CPecdkSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
 
 #[no_mangle]
-pub extern "C" fn pecdkGenSecretKey(num_keyword: usize) -> CPecdkSecretKey {
-    let mut rng = OsRng;
-    let sk = SecretKey::<Bls12>::gen(&mut rng, num_keyword);
-    let sk_str = serde_json::to_string(&sk)
-        .expect("Fail to convert a secret key to a string in pecdk_gen_secret_key");
-    CPecdkSecretKey {
-        ptr: str2ptr(sk_str),
-    }
+pub extern "C" fn pecdkGenSecretKeyFromSeed(
+    seed: CSeed,
+    num_keyword: usize,
+    secret_key: *mut CPecdkSecretKey,
+) -> CErrorCode {
+    ffi_output("pecdkGenSecretKeyFromSeed", secret_key, || {
+        let sk = SecretKey::<CurveEngine>::from_seed(&seed.bytes, num_keyword);
+        Ok(CPecdkSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn pecdkGenPublicKey(secret_key: CPecdkSecretKey) -> CPecdkPublicKey {
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkPublicKey {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let pk = sk.into_public_key();
-    let pk_str = serde_json::to_string(&pk)
-        .expect("Fail to convert a public key to a string in pecdk_gen_public_key");
-    CPecdkPublicKey {
-        ptr: str2ptr(pk_str),
-    }
+pub extern "C" fn pecdkGenPublicKey(
+    secret_key: CPecdkSecretKey,
+    public_key: *mut CPecdkPublicKey,
+) -> CErrorCode {
+    ffi_output("pecdkGenPublicKey", public_key, || {
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let pk = sk.into_public_key();
+        Ok(CPecdkPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn pecdkEncryptKeyword(
     public_key: CPecdkPublicKey,
     keywords: *mut *mut c_char,
-) -> CPecdkCiphertext {
+    num_keyword: usize,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("pecdkEncryptKeyword", ciphertext, || {
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let keywords = ptrs2strs(keywords, num_keyword)?
+            .into_iter()
+            .map(|keyword| keyword.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        encrypt_keyword_generic(pk, keywords)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn pecdkEncryptKeywordBytes(
+    public_key: CPecdkPublicKey,
+    keywords: *const *const u8,
+    keyword_lens: *const usize,
+    num_keyword: usize,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("pecdkEncryptKeywordBytes", ciphertext, || {
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let keywords = ptrs2bytes_vec(keywords, keyword_lens, num_keyword)?;
+        encrypt_keyword_generic(pk, keywords)
+    })
+}
+
+fn encrypt_keyword_generic(
+    pk: PublicKey<CurveEngine>,
+    keywords: Vec<Vec<u8>>,
+) -> Result<CPecdkCiphertext, CError> {
     let mut rng = OsRng;
-    let pk = match serde_json::from_str::<PublicKey<Bls12>>(&ptr2str(public_key.ptr)) {
-        Ok(pk) => pk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let num_keyword = pk.num_keyword();
-    let keyword_slice = unsafe { slice::from_raw_parts(keywords, num_keyword) };
-    let keywords = keyword_slice
-        .into_iter()
-        .map(|keyword| ptr2str(*keyword).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
     let ct = pk
-        .encrypt::<OsRng, Fr>(keywords, &mut rng)
-        .expect("Fail to generate a ciphertext in peks_encrypt_keyword");
-    let ct_str = serde_json::to_string(&ct)
-        .expect("Fail to convert a ciphertext to a string in pecdk_encrypt_keyword");
-    CPecdkCiphertext {
-        ptr: str2ptr(ct_str),
-    }
+        .encrypt::<OsRng, CurveFr>(keywords, &mut rng)
+        .map_err(CError::crypto)?;
+    Ok(CPecdkCiphertext {
+        ptr: to_json_ptr(&ct)?,
+    })
+}
+
+/// Encrypts `keywords` so that they only match trapdoors naming the sender of `sender_secret_key`.
+#[no_mangle]
+pub extern "C" fn pecdkEncryptKeywordAuthenticated(
+    public_key: CPecdkPublicKey,
+    sender_secret_key: CPaeksSecretKey,
+    receiver_public_key: CPaeksPublicKey,
+    keywords: *mut *mut c_char,
+    num_keyword: usize,
+    ciphertext: *mut CPecdkCiphertext,
+) -> CErrorCode {
+    ffi_output("pecdkEncryptKeywordAuthenticated", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let sender_sk = from_json_ptr::<paeks::SecretKey<CurveEngine>>(sender_secret_key.ptr)?;
+        let receiver_pk = from_json_ptr::<paeks::PublicKey<CurveEngine>>(receiver_public_key.ptr)?;
+        let keywords = ptrs2strs(keywords, num_keyword)?
+            .into_iter()
+            .map(|keyword| keyword.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        let ct = pk
+            .encrypt_authenticated::<OsRng, CurveFr>(&sender_sk, &receiver_pk, keywords, &mut rng)
+            .map_err(CError::crypto)?;
+        Ok(CPecdkCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
 }
 
 #[no_mangle]
@@ -100,74 +152,141 @@ pub extern "C" fn pecdkGenTrapdoor(
     keywords: *mut *mut c_char,
     num_keyword: usize,
     sym: c_int,
-) -> CPecdkTrapdoor {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let num_keyword = match num_keyword.try_into() {
-        Ok(num) => num,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPecdkTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let keyword_slice = unsafe { slice::from_raw_parts(keywords, num_keyword) };
-    let keywords = keyword_slice
-        .into_iter()
-        .map(|keyword| ptr2str(*keyword).as_bytes().to_vec())
-        .collect::<Vec<Vec<u8>>>();
-    let sym = match sym {
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("pecdkGenTrapdoor", trapdoor, || {
+        let keywords = ptrs2strs(keywords, num_keyword)?
+            .into_iter()
+            .map(|keyword| keyword.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        gen_trapdoor_generic(secret_key, keywords, c_int2sym(sym))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn pecdkGenTrapdoorBytes(
+    secret_key: CPecdkSecretKey,
+    keywords: *const *const u8,
+    keyword_lens: *const usize,
+    num_keyword: usize,
+    sym: c_int,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("pecdkGenTrapdoorBytes", trapdoor, || {
+        let keywords = ptrs2bytes_vec(keywords, keyword_lens, num_keyword)?;
+        gen_trapdoor_generic(secret_key, keywords, c_int2sym(sym))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn pecdkGenThresholdTrapdoor(
+    secret_key: CPecdkSecretKey,
+    keywords: *mut *mut c_char,
+    num_keyword: usize,
+    threshold: usize,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("pecdkGenThresholdTrapdoor", trapdoor, || {
+        let keywords = ptrs2strs(keywords, num_keyword)?
+            .into_iter()
+            .map(|keyword| keyword.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        gen_trapdoor_generic(secret_key, keywords, SearchSym::Threshold(threshold))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn pecdkGenThresholdTrapdoorBytes(
+    secret_key: CPecdkSecretKey,
+    keywords: *const *const u8,
+    keyword_lens: *const usize,
+    num_keyword: usize,
+    threshold: usize,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("pecdkGenThresholdTrapdoorBytes", trapdoor, || {
+        let keywords = ptrs2bytes_vec(keywords, keyword_lens, num_keyword)?;
+        gen_trapdoor_generic(secret_key, keywords, SearchSym::Threshold(threshold))
+    })
+}
+
+/// Generates a trapdoor matching only ciphertexts from the sender of `sender_public_key`.
+#[no_mangle]
+pub extern "C" fn pecdkGenTrapdoorAuthenticated(
+    secret_key: CPecdkSecretKey,
+    receiver_secret_key: CPaeksSecretKey,
+    sender_public_key: CPaeksPublicKey,
+    keywords: *mut *mut c_char,
+    num_keyword: usize,
+    sym: c_int,
+    trapdoor: *mut CPecdkTrapdoor,
+) -> CErrorCode {
+    ffi_output("pecdkGenTrapdoorAuthenticated", trapdoor, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let receiver_sk = from_json_ptr::<paeks::SecretKey<CurveEngine>>(receiver_secret_key.ptr)?;
+        let sender_pk = from_json_ptr::<paeks::PublicKey<CurveEngine>>(sender_public_key.ptr)?;
+        let keywords = ptrs2strs(keywords, num_keyword)?
+            .into_iter()
+            .map(|keyword| keyword.as_bytes().to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        let td = sk
+            .gen_authenticated_trapdoor::<OsRng, CurveFr>(
+                &receiver_sk,
+                &sender_pk,
+                keywords,
+                c_int2sym(sym),
+                &mut rng,
+            )
+            .map_err(CError::crypto)?;
+        Ok(CPecdkTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
+}
+
+fn c_int2sym(sym: c_int) -> SearchSym {
+    match sym {
         0 => SearchSym::AND,
         _ => SearchSym::OR,
-    };
-    let td = sk
-        .gen_trapdoor::<OsRng, Fr>(keywords, sym, &mut rng)
-        .expect("Fail to generate a trapdoor in pecdk_gen_trapdoor");
-    let td_str = serde_json::to_string(&td)
-        .expect("Fail to convert a trapdoor to a string in peks_gen_trapdoor");
-    CPecdkTrapdoor {
-        ptr: str2ptr(td_str),
     }
 }
 
+fn gen_trapdoor_generic(
+    secret_key: CPecdkSecretKey,
+    keywords: Vec<Vec<u8>>,
+    sym: SearchSym,
+) -> Result<CPecdkTrapdoor, CError> {
+    let mut rng = OsRng;
+    let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+    let td = sk
+        .gen_trapdoor::<OsRng, CurveFr>(keywords, sym, &mut rng)
+        .map_err(|err| match err {
+            PECDKError::InvalidThreshold(..) => CError::new(CErrorCode::InvalidArgument, err),
+            err => CError::crypto(err),
+        })?;
+    Ok(CPecdkTrapdoor {
+        ptr: to_json_ptr(&td)?,
+    })
+}
+
 #[no_mangle]
-pub extern "C" fn pecdkTest(ciphertext: CPecdkCiphertext, trapdoor: CPecdkTrapdoor) -> c_int {
-    let ct = match serde_json::from_str::<Ciphertext<Bls12>>(&ptr2str(ciphertext.ptr)) {
-        Ok(ct) => ct,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return -1;
-        }
-    };
-    let td = match serde_json::from_str::<Trapdoor<Bls12>>(&ptr2str(trapdoor.ptr)) {
-        Ok(td) => td,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return -1;
-        }
-    };
-    let tested = td
-        .test(&ct)
-        .expect("Fail to test the ciphertext with the trapdoor in peks_test");
-    if tested {
-        1
-    } else {
-        0
-    }
+pub extern "C" fn pecdkTest(
+    ciphertext: CPecdkCiphertext,
+    trapdoor: CPecdkTrapdoor,
+    result: *mut bool,
+) -> CErrorCode {
+    ffi_output("pecdkTest", result, || {
+        let ct = from_json_ptr::<Ciphertext<CurveEngine>>(ciphertext.ptr)?;
+        let td = from_json_ptr::<Trapdoor<CurveEngine>>(trapdoor.ptr)?;
+        td.test(&ct).map_err(CError::crypto)
+    })
 }
 
+/// Overwrites the serialized secret key with zeros before freeing it.
 #[no_mangle]
 pub extern "C" fn pecdkFreeSecretKey(secret_key: CPecdkSecretKey) {
-    drop_ptr(secret_key.ptr);
+    drop_secret_ptr(secret_key.ptr);
 }
 
 #[no_mangle]
@@ -0,0 +1,121 @@
+use super::*;
+
+/// A trapdoor for "contains all of the positive keywords but none of the negative keywords".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
+))]
+pub struct CompositeTrapdoor<E: Engine> {
+    positive: Option<Trapdoor<E>>,
+    negative: Trapdoor<E>,
+}
+
+impl<E: Engine> SecretKey<E> {
+    pub fn gen_composite_trapdoor<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        mut positive_keywords: Vec<Vec<u8>>,
+        mut negative_keywords: Vec<Vec<u8>>,
+        rng: &mut R,
+    ) -> Result<CompositeTrapdoor<E>, PECDKError<E>> {
+        // A repeated keyword would count once in the ciphertext but twice in an AND trapdoor.
+        positive_keywords.sort();
+        positive_keywords.dedup();
+        negative_keywords.sort();
+        negative_keywords.dedup();
+        let positive = if positive_keywords.is_empty() {
+            None
+        } else {
+            Some(self.gen_trapdoor::<R, F>(positive_keywords, SearchSym::AND, rng)?)
+        };
+        let negative = self.gen_trapdoor::<R, F>(negative_keywords, SearchSym::OR, rng)?;
+        Ok(CompositeTrapdoor { positive, negative })
+    }
+}
+
+impl<E: Engine> CompositeTrapdoor<E> {
+    pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PECDKError<E>> {
+        if let Some(positive) = self.positive.as_ref() {
+            if !positive.test(ct)? {
+                return Ok(false);
+            }
+        }
+        Ok(!self.negative.test(ct)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_composite_and_not() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let (a, b, c) = (b"A".to_vec(), b"B".to_vec(), b"C".to_vec());
+        let ct_ab = public_key
+            .encrypt::<_, Fr>(vec![a.clone(), b.clone()], &mut rng)
+            .unwrap();
+        let ct_abc = public_key
+            .encrypt::<_, Fr>(vec![a.clone(), b.clone(), c.clone()], &mut rng)
+            .unwrap();
+        let ct_ac = public_key
+            .encrypt::<_, Fr>(vec![a.clone(), c.clone()], &mut rng)
+            .unwrap();
+
+        let trapdoor = secret_key
+            .gen_composite_trapdoor::<_, Fr>(vec![a.clone(), b], vec![c.clone()], &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct_ab).unwrap());
+        assert!(!trapdoor.test(&ct_abc).unwrap());
+        assert!(!trapdoor.test(&ct_ac).unwrap());
+
+        let trapdoor = secret_key
+            .gen_composite_trapdoor::<_, Fr>(vec![], vec![c], &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct_ab).unwrap());
+        assert!(!trapdoor.test(&ct_abc).unwrap());
+
+        let trapdoor = secret_key
+            .gen_composite_trapdoor::<_, Fr>(vec![a], vec![], &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct_ab).unwrap());
+        assert!(trapdoor.test(&ct_ac).unwrap());
+
+        let json = serde_json::to_string(&trapdoor).unwrap();
+        let decoded: CompositeTrapdoor<Bls12> = serde_json::from_str(&json).unwrap();
+        assert!(decoded.test(&ct_abc).unwrap());
+    }
+
+    #[test]
+    fn test_composite_duplicated_keyword() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let (a, b, c) = (b"A".to_vec(), b"B".to_vec(), b"C".to_vec());
+        let ct_ab = public_key
+            .encrypt::<_, Fr>(vec![a.clone(), b.clone()], &mut rng)
+            .unwrap();
+        let ct_abc = public_key
+            .encrypt::<_, Fr>(vec![a.clone(), b.clone(), c.clone()], &mut rng)
+            .unwrap();
+
+        let trapdoor = secret_key
+            .gen_composite_trapdoor::<_, Fr>(vec![a.clone(), b, a], vec![c.clone(), c], &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct_ab).unwrap());
+        assert!(!trapdoor.test(&ct_abc).unwrap());
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use crate::payload::SealedPayload;
+
+const ENVELOPE_INFO: &[u8] = b"pecdk_envelope";
+
+/// A searchable ciphertext with an attached AEAD-encrypted payload.
+/// The payload key is encapsulated as `g2^r` with the shared secret `mue^r`,
+/// which only the secret key holder can recompute as `e(g1, g2^r)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "E::G2Affine: Deserialize<'de>"))]
+pub struct Envelope<E: Engine> {
+    ciphertext: Ciphertext<E>,
+    encapsulated_key: E::G2Affine,
+    payload: SealedPayload,
+}
+
+impl<E: Engine> PublicKey<E> {
+    pub fn encrypt_envelope<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        keywords: Vec<Vec<u8>>,
+        payload: &[u8],
+        rng: &mut R,
+    ) -> Result<Envelope<E>, PECDKError<E>> {
+        let ciphertext = self.encrypt::<R, F>(keywords, rng)?;
+        let r = <E::Fr as Field>::random(rng);
+        let encapsulated_key = self.g2.mul(r).into_affine();
+        let shared = hash_field2bytes::<E>(self.mue.pow(r.into_repr()))?;
+        let aad = envelope_aad(&ciphertext, &encapsulated_key);
+        let payload = SealedPayload::seal(&shared, ENVELOPE_INFO, &aad, payload, rng)?;
+        Ok(Envelope {
+            ciphertext,
+            encapsulated_key,
+            payload,
+        })
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    pub fn open_envelope(&self, envelope: &Envelope<E>) -> Result<Vec<u8>, PECDKError<E>> {
+        let shared = hash_field2bytes::<E>(E::pairing(
+            self.g1.into_projective(),
+            envelope.encapsulated_key.into_projective(),
+        ))?;
+        let aad = envelope_aad(&envelope.ciphertext, &envelope.encapsulated_key);
+        Ok(envelope.payload.open(&shared, ENVELOPE_INFO, &aad)?)
+    }
+}
+
+impl<E: Engine> Envelope<E> {
+    pub fn ciphertext(&self) -> &Ciphertext<E> {
+        &self.ciphertext
+    }
+
+    pub fn test(&self, trapdoor: &Trapdoor<E>) -> Result<bool, PECDKError<E>> {
+        trapdoor.test(&self.ciphertext)
+    }
+}
+
+/// Binds the payload to the searchable tags so that they cannot be swapped between envelopes.
+fn envelope_aad<E: Engine>(ct: &Ciphertext<E>, encapsulated_key: &E::G2Affine) -> Vec<u8> {
+    let mut aad = encapsulated_key.into_compressed().as_ref().to_vec();
+    for bytes in ct.d_bytes.iter() {
+        aad.extend_from_slice(bytes);
+    }
+    aad
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_pecdk_envelope() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 2;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let payload = b"record body".to_vec();
+        let envelope = public_key
+            .encrypt_envelope::<_, Fr>(vec![b"A".to_vec(), b"B".to_vec()], &payload, &mut rng)
+            .unwrap();
+        let other = public_key
+            .encrypt_envelope::<_, Fr>(vec![b"C".to_vec()], b"other", &mut rng)
+            .unwrap();
+
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(vec![b"A".to_vec()], SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(envelope.test(&trapdoor).unwrap());
+        assert!(!other.test(&trapdoor).unwrap());
+        assert_eq!(secret_key.open_envelope(&envelope).unwrap(), payload);
+
+        let json = serde_json::to_string(&envelope).unwrap();
+        let decoded: Envelope<Bls12> = serde_json::from_str(&json).unwrap();
+        assert_eq!(secret_key.open_envelope(&decoded).unwrap(), payload);
+
+        let mut swapped = envelope.clone();
+        swapped.ciphertext = other.ciphertext.clone();
+        assert!(secret_key.open_envelope(&swapped).is_err());
+        let wrong_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        assert!(wrong_key.open_envelope(&envelope).is_err());
+    }
+}
@@ -0,0 +1,482 @@
+use super::*;
+use digest::Digest;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use sha2::Sha256;
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::fmt;
+
+/// Master secret from which the PECDK key of every epoch is derived.
+/// `g1` and `g2` are shared by all epochs, while `alphas`, `betas` and `theta` depend on the epoch.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MasterSecretKey {
+    seed: [u8; 32],
+    num_keyword: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::Fr: Deserialize<'de>, E::G1Affine: Deserialize<'de>, E::G2Affine: Deserialize<'de>"
+))]
+pub struct EpochSecretKey<E: Engine> {
+    epoch: u64,
+    secret_key: SecretKey<E>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::G2Affine: Deserialize<'de>, E::Fqk: Deserialize<'de>"
+))]
+pub struct EpochPublicKey<E: Engine> {
+    epoch: u64,
+    public_key: PublicKey<E>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "E::G2Affine: Deserialize<'de>"))]
+pub struct EpochCiphertext<E: Engine> {
+    epoch: u64,
+    ciphertext: Ciphertext<E>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
+))]
+pub struct EpochTrapdoor<E: Engine> {
+    epoch: u64,
+    trapdoor: Trapdoor<E>,
+}
+
+/// Moves ciphertexts from one epoch to another without the keywords.
+/// With `g2^r = C_i^{1/theta}`, each slot is re-tagged as
+/// `A_ij * C_i^{(alpha'_j - alpha_j) / theta}`, `B_ij^{beta'_j / beta_j}` and `C_i^{theta' / theta}`,
+/// so trapdoors of the old epoch no longer match, and its digest is recomputed from
+/// `e(g1^{1/theta}, C_i) = mue^r` under the new epoch label.
+/// Its scalars would also rotate ciphertexts back, so it never leaves `MasterSecretKey`.
+struct Rotation<E: Engine> {
+    from: u64,
+    to: u64,
+    alpha_shifts: Vec<E::Fr>,
+    beta_ratios: Vec<E::Fr>,
+    theta_ratio: E::Fr,
+    g1_theta_inv: E::G1Affine,
+}
+
+const EPOCH_LABEL: &[u8] = b"pecdk_epoch_label";
+
+/// Hashed into every slot digest, so a ciphertext only matches trapdoors of the epoch it was
+/// encrypted or rotated for, whatever its plaintext `epoch` says.
+fn epoch_label(epoch: u64) -> Vec<u8> {
+    let mut label = EPOCH_LABEL.to_vec();
+    label.extend_from_slice(&epoch.to_be_bytes());
+    label
+}
+
+impl MasterSecretKey {
+    pub fn gen<R: RngCore>(rng: &mut R, num_keyword: usize) -> Self {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        Self { seed, num_keyword }
+    }
+
+    pub fn num_keyword(&self) -> usize {
+        self.num_keyword
+    }
+
+    pub fn epoch_secret_key<E: Engine>(&self, epoch: u64) -> EpochSecretKey<E> {
+        let mut rng = self.derive_rng(b"pecdk_generators", &[]);
+        let g1 = <E::G1 as CurveProjective>::random(&mut rng).into_affine();
+        let g2 = <E::G2 as CurveProjective>::random(&mut rng).into_affine();
+
+        let mut rng = self.derive_rng(b"pecdk_epoch", &epoch.to_be_bytes());
+        let n = self.num_keyword;
+        let alphas = (0..n + 1)
+            .map(|_| <E::Fr as Field>::random(&mut rng))
+            .collect::<Vec<E::Fr>>();
+        let betas = (0..n + 1)
+            .map(|_| <E::Fr as Field>::random(&mut rng))
+            .collect::<Vec<E::Fr>>();
+        let theta = <E::Fr as Field>::random(&mut rng);
+        EpochSecretKey {
+            epoch,
+            secret_key: SecretKey {
+                alphas,
+                betas,
+                theta,
+                g1,
+                g2,
+            },
+        }
+    }
+
+    /// Re-encrypts `ct` for the epoch `to` without its keywords.
+    /// Only the master secret holder can rotate, so no token is handed to ciphertext holders.
+    pub fn rotate<E: Engine>(
+        &self,
+        ct: &EpochCiphertext<E>,
+        to: u64,
+    ) -> Result<EpochCiphertext<E>, PECDKError<E>> {
+        self.rotation::<E>(ct.epoch, to)?.rotate(ct)
+    }
+
+    /// Like `rotate`, deriving the keys of each source epoch once.
+    pub fn rotate_batch<E: Engine>(
+        &self,
+        cts: &[EpochCiphertext<E>],
+        to: u64,
+    ) -> Result<Vec<EpochCiphertext<E>>, PECDKError<E>> {
+        let mut rotations = BTreeMap::new();
+        cts.iter()
+            .map(|ct| {
+                let rotation = match rotations.entry(ct.epoch) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.rotation::<E>(ct.epoch, to)?),
+                };
+                rotation.rotate(ct)
+            })
+            .collect()
+    }
+
+    fn rotation<E: Engine>(&self, from: u64, to: u64) -> Result<Rotation<E>, PECDKError<E>> {
+        let old = self.epoch_secret_key::<E>(from).secret_key;
+        let new = self.epoch_secret_key::<E>(to).secret_key;
+        let theta_inv = old
+            .theta
+            .inverse()
+            .ok_or(PECDKError::InverseFrError(old.theta))?;
+        let alpha_shifts = old
+            .alphas
+            .iter()
+            .zip(new.alphas.iter())
+            .map(|(old_alpha, new_alpha)| {
+                let mut shift = *new_alpha;
+                shift.sub_assign(old_alpha);
+                shift.mul_assign(&theta_inv);
+                shift
+            })
+            .collect();
+        let beta_ratios = old
+            .betas
+            .iter()
+            .zip(new.betas.iter())
+            .map(|(old_beta, new_beta)| {
+                let mut ratio = old_beta
+                    .inverse()
+                    .ok_or(PECDKError::InverseFrError(*old_beta))?;
+                ratio.mul_assign(new_beta);
+                Ok(ratio)
+            })
+            .collect::<Result<Vec<E::Fr>, PECDKError<E>>>()?;
+        let mut theta_ratio = new.theta;
+        theta_ratio.mul_assign(&theta_inv);
+        let g1_theta_inv = old.g1.mul(theta_inv).into_affine();
+        Ok(Rotation {
+            from,
+            to,
+            alpha_shifts,
+            beta_ratios,
+            theta_ratio,
+            g1_theta_inv,
+        })
+    }
+
+    fn derive_rng(&self, domain: &[u8], label: &[u8]) -> ChaCha20Rng {
+        let mut hasher = Sha256::new();
+        hasher.update(domain);
+        hasher.update(self.seed);
+        hasher.update(label);
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&hasher.finalize());
+        ChaCha20Rng::from_seed(seed)
+    }
+}
+
+impl fmt::Debug for MasterSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterSecretKey")
+            .field("num_keyword", &self.num_keyword)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for MasterSecretKey {
+    fn drop(&mut self) {
+        wipe(&mut self.seed, 0);
+    }
+}
+
+impl<E: Engine> EpochSecretKey<E> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn secret_key(&self) -> &SecretKey<E> {
+        &self.secret_key
+    }
+
+    pub fn into_public_key(&self) -> EpochPublicKey<E> {
+        EpochPublicKey {
+            epoch: self.epoch,
+            public_key: self.secret_key.into_public_key(),
+        }
+    }
+
+    pub fn gen_trapdoor<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        keywords: Vec<Vec<u8>>,
+        sym: SearchSym,
+        rng: &mut R,
+    ) -> Result<EpochTrapdoor<E>, PECDKError<E>> {
+        Ok(EpochTrapdoor {
+            epoch: self.epoch,
+            trapdoor: self.secret_key.gen_trapdoor::<R, F>(keywords, sym, rng)?,
+        })
+    }
+}
+
+impl<E: Engine> EpochPublicKey<E> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn public_key(&self) -> &PublicKey<E> {
+        &self.public_key
+    }
+
+    pub fn encrypt<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        keywords: Vec<Vec<u8>>,
+        rng: &mut R,
+    ) -> Result<EpochCiphertext<E>, PECDKError<E>> {
+        let n = self.public_key.num_keyword();
+        if keywords.len() > n {
+            return Err(PECDKError::ExcessiveNumberOfKeywords(keywords.len(), n));
+        }
+        let hashed_words = hash_keywords::<E, F>(keywords)?;
+        let label = epoch_label(self.epoch);
+        Ok(EpochCiphertext {
+            epoch: self.epoch,
+            ciphertext: self.public_key.encrypt_hashed(hashed_words, &label, rng)?,
+        })
+    }
+}
+
+impl<E: Engine> EpochCiphertext<E> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn ciphertext(&self) -> &Ciphertext<E> {
+        &self.ciphertext
+    }
+}
+
+impl<E: Engine> EpochTrapdoor<E> {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn trapdoor(&self) -> &Trapdoor<E> {
+        &self.trapdoor
+    }
+
+    pub fn test(&self, ct: &EpochCiphertext<E>) -> Result<bool, PECDKError<E>> {
+        if ct.epoch != self.epoch {
+            return Err(PECDKError::EpochMismatch(ct.epoch, self.epoch));
+        }
+        let (t1s, neg_t2s) = self.trapdoor.prepare_points();
+        let label = epoch_label(self.epoch);
+        self.trapdoor
+            .test_with_prepared(&t1s, &neg_t2s, &ct.ciphertext, &label)
+    }
+}
+
+impl<E: Engine> Rotation<E> {
+    fn rotate(&self, ct: &EpochCiphertext<E>) -> Result<EpochCiphertext<E>, PECDKError<E>> {
+        let n = self.beta_ratios.len() - 1;
+        let old = &ct.ciphertext;
+        for slots in [
+            old.a_points.len(),
+            old.b_points.len(),
+            old.c_points.len(),
+            old.d_bytes.len(),
+        ] {
+            if slots != n {
+                return Err(PECDKError::SlotCountMismatch(slots, n));
+            }
+        }
+        let from_label = epoch_label(self.from);
+        let to_label = epoch_label(self.to);
+        let d_bytes = old
+            .c_points
+            .par_iter()
+            .zip(old.d_bytes.par_iter())
+            .map(|(c_point, d_bytes)| {
+                let mue_r = E::pairing(self.g1_theta_inv, *c_point);
+                let hashed = hash_labeled_field2bytes::<E>(&from_label, mue_r)?;
+                if !digest_eq(&hashed, d_bytes) {
+                    return Err(PECDKError::EpochTagMismatch(self.from));
+                }
+                Ok(hash_labeled_field2bytes::<E>(&to_label, mue_r)?)
+            })
+            .collect::<Result<Vec<Vec<u8>>, PECDKError<E>>>()?;
+        let a_points = old
+            .a_points
+            .par_iter()
+            .zip(old.c_points.par_iter())
+            .map(|(a_point_vec, c_point)| {
+                a_point_vec
+                    .iter()
+                    .zip(self.alpha_shifts.iter())
+                    .map(|(a_point, shift)| {
+                        let mut point = a_point.into_projective();
+                        point.add_assign(&c_point.mul(*shift));
+                        point.into_affine()
+                    })
+                    .collect()
+            })
+            .collect();
+        let b_points = old
+            .b_points
+            .par_iter()
+            .map(|b_point_vec| {
+                b_point_vec
+                    .iter()
+                    .zip(self.beta_ratios.iter())
+                    .map(|(b_point, ratio)| b_point.mul(*ratio).into_affine())
+                    .collect()
+            })
+            .collect();
+        let c_points = old
+            .c_points
+            .par_iter()
+            .map(|c_point| c_point.mul(self.theta_ratio).into_affine())
+            .collect();
+        Ok(EpochCiphertext {
+            epoch: self.to,
+            ciphertext: Ciphertext {
+                a_points,
+                b_points,
+                c_points,
+                d_bytes,
+            },
+        })
+    }
+}
+
+impl<E: Engine> Drop for Rotation<E> {
+    fn drop(&mut self) {
+        let zero = <E::Fr as Field>::zero();
+        wipe(&mut self.alpha_shifts, zero);
+        wipe(&mut self.beta_ratios, zero);
+        wipe(std::slice::from_mut(&mut self.theta_ratio), zero);
+        wipe(
+            std::slice::from_mut(&mut self.g1_theta_inv),
+            E::G1Affine::zero(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_pecdk_epoch_rotation() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 3;
+        let master = MasterSecretKey::gen(&mut rng, n);
+        let sk0 = master.epoch_secret_key::<Bls12>(0);
+        let sk1 = master.epoch_secret_key::<Bls12>(1);
+        assert_eq!(
+            serde_json::to_string(&sk0).unwrap(),
+            serde_json::to_string(&master.epoch_secret_key::<Bls12>(0)).unwrap()
+        );
+        let pk0 = sk0.into_public_key();
+        let keywords = vec![b"A".to_vec(), b"B".to_vec()];
+        let ct0 = pk0.encrypt::<_, Fr>(keywords.clone(), &mut rng).unwrap();
+        let leaked = sk0
+            .gen_trapdoor::<_, Fr>(vec![b"A".to_vec()], SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(leaked.test(&ct0).unwrap());
+
+        let ct1 = master.rotate(&ct0, 1).unwrap();
+        assert_eq!(ct1.epoch(), 1);
+        assert!(matches!(
+            leaked.test(&ct1),
+            Err(PECDKError::EpochMismatch(1, 0))
+        ));
+        let relabeled_leaked = EpochTrapdoor {
+            epoch: 1,
+            trapdoor: leaked.trapdoor().clone(),
+        };
+        assert!(!relabeled_leaked.test(&ct1).unwrap());
+
+        let trapdoor = sk1
+            .gen_trapdoor::<_, Fr>(keywords, SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct1).unwrap());
+        let fresh = sk1
+            .into_public_key()
+            .encrypt::<_, Fr>(vec![b"A".to_vec(), b"B".to_vec()], &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&fresh).unwrap());
+
+        let batch = master.rotate_batch(&[ct0, ct1.clone()], 2).unwrap();
+        let sk2 = master.epoch_secret_key::<Bls12>(2);
+        let trapdoor2 = sk2
+            .gen_trapdoor::<_, Fr>(vec![b"B".to_vec()], SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(batch.iter().all(|ct| trapdoor2.test(ct).unwrap()));
+    }
+
+    #[test]
+    fn test_pecdk_epoch_binding() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 2;
+        let master = MasterSecretKey::gen(&mut rng, n);
+        let sk1 = master.epoch_secret_key::<Bls12>(1);
+        let ct1 = sk1
+            .into_public_key()
+            .encrypt::<_, Fr>(vec![b"A".to_vec()], &mut rng)
+            .unwrap();
+        let trapdoor = sk1
+            .gen_trapdoor::<_, Fr>(vec![b"A".to_vec()], SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct1).unwrap());
+
+        // Editing both epochs leaves the keys unchanged, but the slot digests are bound to epoch 1.
+        let relabeled_ct = EpochCiphertext {
+            epoch: 0,
+            ciphertext: ct1.ciphertext().clone(),
+        };
+        let relabeled_trapdoor = EpochTrapdoor {
+            epoch: 0,
+            trapdoor: trapdoor.trapdoor().clone(),
+        };
+        assert!(!relabeled_trapdoor.test(&relabeled_ct).unwrap());
+        assert!(!trapdoor.trapdoor().test(ct1.ciphertext()).unwrap());
+        assert!(matches!(
+            master.rotate(&relabeled_ct, 2),
+            Err(PECDKError::EpochTagMismatch(0))
+        ));
+
+        let other = MasterSecretKey::gen(&mut rng, n + 1);
+        assert!(matches!(
+            other.rotate(&ct1, 2),
+            Err(PECDKError::SlotCountMismatch(2, 3))
+        ));
+    }
+}
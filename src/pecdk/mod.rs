@@ -1,11 +1,27 @@
+mod authenticated;
+mod batch;
 #[cfg(feature = "c_api")]
 mod c_api;
+mod composite;
+mod envelope;
+mod epoch;
+mod prepared;
+mod query;
 
+use crate::seed::{seed_rng, SEED_SIZE};
 use crate::utils::polynomial_from_roots;
-use crate::{hashes::*, BaseROFr};
+use crate::wipe::wipe;
+use crate::wire::*;
+use crate::{hashes::*, BaseROFr, EngineId, PayloadError};
 
+pub use batch::*;
 #[cfg(feature = "c_api")]
 pub use c_api::*;
+pub use composite::*;
+pub use envelope::*;
+pub use epoch::*;
+pub use prepared::*;
+pub use query::*;
 
 use fff::{Field, PrimeField};
 use groupy::{CurveAffine, CurveProjective};
@@ -14,6 +30,7 @@ use rand::seq::SliceRandom;
 use rand_core::RngCore;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,11 +39,27 @@ pub enum PECDKError<E: Engine> {
     InverseFrError(E::Fr),
     #[error("Fail to compute the inversed Fqk value `{0}`")]
     InverseFqkError(E::Fqk),
+    #[error("The number of given keywords is {0}, but the max number is {1}")]
+    ExcessiveNumberOfKeywords(usize, usize),
+    #[error("Fail to compute the final exponentiation of `{0}`")]
+    FinalExponentiationError(E::Fqk),
+    #[error("The threshold {0} must be between 1 and the number of keywords {1}")]
+    InvalidThreshold(usize, usize),
+    #[error("The epoch is {0}, but {1} is expected")]
+    EpochMismatch(u64, u64),
+    #[error("The ciphertext has {0} slots, but the key has {1}")]
+    SlotCountMismatch(usize, usize),
+    #[error("The ciphertext is not bound to its epoch {0}")]
+    EpochTagMismatch(u64),
+    #[error("The slot has {0} points, but the trapdoor has {1}")]
+    PointCountMismatch(usize, usize),
     #[error(transparent)]
     ECHashError(#[from] ECHashError),
+    #[error(transparent)]
+    PayloadError(#[from] PayloadError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SecretKey<E: Engine> {
     alphas: Vec<E::Fr>,
     betas: Vec<E::Fr>,
@@ -45,7 +78,7 @@ pub struct PublicKey<E: Engine> {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(bound(deserialize = "'de: 'static"))]
+#[serde(bound(deserialize = "E::G2Affine: Deserialize<'de>"))]
 pub struct Ciphertext<E: Engine> {
     a_points: Vec<Vec<E::G2Affine>>,
     b_points: Vec<Vec<E::G2Affine>>,
@@ -54,7 +87,7 @@ pub struct Ciphertext<E: Engine> {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(bound(deserialize = "'de: 'static"))]
+#[serde(bound(deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"))]
 pub struct Trapdoor<E: Engine> {
     t1s: Vec<E::G1Affine>,
     t2s: Vec<E::G1Affine>,
@@ -66,9 +99,21 @@ pub struct Trapdoor<E: Engine> {
 pub enum SearchSym {
     AND,
     OR,
+    /// Matches when at least the given number of the trapdoor's keywords are in the ciphertext.
+    Threshold(usize),
 }
 
 const TAG: &'static str = "pecdk_hash_to_field";
+const SEED_DOMAIN: &[u8] = b"pecdk_secret_key";
+
+pub(crate) fn hash_keywords<E: Engine, F: BaseROFr<E>>(
+    keywords: Vec<Vec<u8>>,
+) -> Result<Vec<E::Fr>, ECHashError> {
+    keywords
+        .into_par_iter()
+        .map(|word| hash_bytes2field::<F, E>(&word, TAG.as_bytes()))
+        .collect()
+}
 
 impl<E: Engine> SecretKey<E> {
     pub fn gen<R: RngCore>(rng: &mut R, num_keyword: usize) -> Self {
@@ -91,6 +136,11 @@ impl<E: Engine> SecretKey<E> {
         }
     }
 
+    /// Derives the secret key deterministically from `seed`.
+    pub fn from_seed(seed: &[u8; SEED_SIZE], num_keyword: usize) -> Self {
+        Self::gen(&mut seed_rng(SEED_DOMAIN, seed), num_keyword)
+    }
+
     pub fn num_keyword(&self) -> usize {
         self.alphas.len() - 1
     }
@@ -99,15 +149,13 @@ impl<E: Engine> SecretKey<E> {
         let g2 = self.g2;
         let x_points = self
             .alphas
-            .clone()
-            .into_iter()
-            .map(|s: E::Fr| g2.mul(s).into_affine())
+            .iter()
+            .map(|s| g2.mul(*s).into_affine())
             .collect::<Vec<E::G2Affine>>();
         let y_points = self
             .betas
-            .clone()
-            .into_iter()
-            .map(|s: E::Fr| g2.mul(s).into_affine())
+            .iter()
+            .map(|s| g2.mul(*s).into_affine())
             .collect::<Vec<E::G2Affine>>();
         let z_point = g2.mul(self.theta).into_affine();
         let mue = E::pairing(self.g1.into_projective(), g2.into_projective());
@@ -126,19 +174,34 @@ impl<E: Engine> SecretKey<E> {
         sym: SearchSym,
         rng: &mut R,
     ) -> Result<Trapdoor<E>, PECDKError<E>> {
-        let m = keywords.len();
+        let hashes = hash_keywords::<E, F>(keywords)?;
+        self.gen_trapdoor_hashed(hashes, sym, rng)
+    }
+
+    /// Generates a trapdoor from keywords that are already hashed to field elements.
+    pub(crate) fn gen_trapdoor_hashed<R: RngCore>(
+        &self,
+        hashes: Vec<E::Fr>,
+        sym: SearchSym,
+        rng: &mut R,
+    ) -> Result<Trapdoor<E>, PECDKError<E>> {
+        let m = hashes.len();
+        let n = self.num_keyword();
+        if m > n {
+            return Err(PECDKError::ExcessiveNumberOfKeywords(m, n));
+        }
+        if let SearchSym::Threshold(k) = sym {
+            if k == 0 || k > m {
+                return Err(PECDKError::InvalidThreshold(k, m));
+            }
+        }
         let zero = <E::Fr as Field>::zero();
         let one = <E::Fr as Field>::one();
         let mut minus_one = zero.clone();
         minus_one.sub_assign(&one);
 
-        let mut keywords = keywords;
-        keywords.shuffle(rng);
-
-        let hashes = keywords
-            .into_par_iter()
-            .map(|word| hash_bytes2field::<F, E>(&word, TAG.as_bytes()))
-            .collect::<Result<Vec<E::Fr>, ECHashError>>()?;
+        let mut hashes = hashes;
+        hashes.shuffle(rng);
 
         let coefficients = polynomial_from_roots(&hashes);
 
@@ -182,6 +245,24 @@ impl<E: Engine> SecretKey<E> {
     }
 }
 
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("num_keyword", &self.num_keyword())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        let zero = <E::Fr as Field>::zero();
+        wipe(&mut self.alphas, zero);
+        wipe(&mut self.betas, zero);
+        wipe(std::slice::from_mut(&mut self.theta), zero);
+        wipe(std::slice::from_mut(&mut self.g1), E::G1Affine::zero());
+    }
+}
+
 impl<E: Engine> PublicKey<E> {
     pub fn num_keyword(&self) -> usize {
         self.x_points.len() - 1
@@ -196,10 +277,32 @@ impl<E: Engine> PublicKey<E> {
         keywords: Vec<Vec<u8>>,
         rng: &mut R,
     ) -> Result<Ciphertext<E>, PECDKError<E>> {
-        let n = self.x_points.len() - 1;
-        assert_eq!(keywords.len(), n);
-        let mut keywords = keywords;
-        keywords.shuffle(rng);
+        let n = self.num_keyword();
+        if keywords.len() > n {
+            return Err(PECDKError::ExcessiveNumberOfKeywords(keywords.len(), n));
+        }
+        let hashed_words = hash_keywords::<E, F>(keywords)?;
+        self.encrypt_hashed(hashed_words, &[], rng)
+    }
+
+    /// Encrypts keywords that are already hashed to field elements.
+    /// `label` is hashed into every slot digest, so only trapdoors tested with it can match.
+    pub(crate) fn encrypt_hashed<R: RngCore>(
+        &self,
+        hashed_words: Vec<E::Fr>,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Ciphertext<E>, PECDKError<E>> {
+        let n = self.num_keyword();
+        if hashed_words.len() > n {
+            return Err(PECDKError::ExcessiveNumberOfKeywords(hashed_words.len(), n));
+        }
+        let mut hashed_words = hashed_words;
+        // Unused slots are filled with fresh random field elements, which match no trapdoor.
+        while hashed_words.len() < n {
+            hashed_words.push(<E::Fr as Field>::random(rng));
+        }
+        hashed_words.shuffle(rng);
 
         let rs = (0..n)
             .map(|_| <E::Fr as Field>::random(rng))
@@ -213,10 +316,9 @@ impl<E: Engine> PublicKey<E> {
         }
         let mut a_points = Vec::with_capacity(n);
         let mut b_points = Vec::with_capacity(n);
-        let tag = TAG.as_bytes();
         for i in 0..n {
             let r: E::Fr = rs[i];
-            let hashed_word = hash_bytes2field::<F, E>(&keywords[i], tag)?;
+            let hashed_word = hashed_words[i];
             let a_point_vec = (0..n + 1)
                 .into_par_iter()
                 .map(|j| {
@@ -247,7 +349,7 @@ impl<E: Engine> PublicKey<E> {
             .collect::<Vec<E::Fqk>>();
         let mut d_bytes = Vec::with_capacity(n);
         for scalar in d_scalars.into_iter() {
-            d_bytes.push(hash_field2bytes::<E>(scalar)?);
+            d_bytes.push(hash_labeled_field2bytes::<E>(label, scalar)?);
         }
         Ok(Ciphertext {
             a_points,
@@ -258,72 +360,310 @@ impl<E: Engine> PublicKey<E> {
     }
 }
 
+pub(crate) type G1Prepared<E> = <<E as Engine>::G1Affine as PairingCurveAffine>::Prepared;
+
 impl<E: Engine> Trapdoor<E> {
     pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PECDKError<E>> {
+        let (t1s, neg_t2s) = self.prepare_points();
+        self.test_with_prepared(&t1s, &neg_t2s, ct, &[])
+    }
+
+    /// Prepares `t1s` and the negations of `t2s`,
+    /// so that the quotient of pairing products needs no inversion.
+    pub(crate) fn prepare_points(&self) -> (Vec<G1Prepared<E>>, Vec<G1Prepared<E>>) {
+        let t1s = self.t1s.par_iter().map(|t1| t1.prepare()).collect();
+        let neg_t2s = self
+            .t2s
+            .par_iter()
+            .map(|t2| {
+                let mut neg_t2 = *t2;
+                neg_t2.negate();
+                neg_t2.prepare()
+            })
+            .collect();
+        (t1s, neg_t2s)
+    }
+
+    /// Evaluates the slots in parallel chunks and stops once the result is decided.
+    /// `label` must be the one the ciphertext was encrypted with.
+    pub(crate) fn test_with_prepared(
+        &self,
+        t1s: &[G1Prepared<E>],
+        neg_t2s: &[G1Prepared<E>],
+        ct: &Ciphertext<E>,
+        label: &[u8],
+    ) -> Result<bool, PECDKError<E>> {
         let n = ct.c_points.len();
         let m = self.t1s.len() - 1;
-        let test1s = (0..n)
-            .into_par_iter()
-            .map(|i| {
-                let c_powed = ct.c_points[i].mul(self.t3);
-                let mut val = E::Fqk::one();
-                for j in 0..(m + 1) {
-                    let mut point2 = ct.a_points[i][j].into_projective();
-                    point2.add_assign(&c_powed);
-                    let paired = E::miller_loop(
-                        [(&(self.t1s[j].prepare()), &(point2.into_affine().prepare()))].iter(),
-                    );
-                    val.mul_assign(&paired);
-                }
-                val
-            })
-            .collect::<Vec<E::Fqk>>();
+        let chunk_size = rayon::current_num_threads().max(1);
+        let mut sum_valid = 0;
+        let mut start = 0;
+        while start < n {
+            let end = (start + chunk_size).min(n);
+            sum_valid += (start..end)
+                .into_par_iter()
+                .map(|i| self.match_slot(t1s, neg_t2s, ct, i, label))
+                .collect::<Result<Vec<bool>, PECDKError<E>>>()?
+                .into_iter()
+                .filter(|is_match| *is_match)
+                .count();
+            start = end;
+            let remaining = n - end;
+            let decided = match self.sym {
+                SearchSym::AND => sum_valid > m || sum_valid + remaining < m,
+                SearchSym::OR => sum_valid >= 1,
+                SearchSym::Threshold(k) => sum_valid >= k || sum_valid + remaining < k,
+            };
+            if decided {
+                break;
+            }
+        }
+        match self.sym {
+            SearchSym::AND => Ok(sum_valid == m),
+            SearchSym::OR => Ok(sum_valid >= 1),
+            SearchSym::Threshold(k) => Ok(sum_valid >= k),
+        }
+    }
 
-        let test2_invs = (0..n)
-            .into_par_iter()
-            .map(|i| {
-                let mut val = E::Fqk::one();
-                for j in 0..(m + 1) {
-                    let paired = E::miller_loop(
-                        [(&(self.t2s[j].prepare()), &(ct.b_points[i][j].prepare()))].iter(),
-                    );
-                    val.mul_assign(&paired);
-                }
-                val.inverse().ok_or(PECDKError::<E>::InverseFqkError(val))
-            })
-            .collect::<Result<Vec<E::Fqk>, PECDKError<E>>>()?;
-        let test_scalars = test1s
+    /// Counts the ciphertext slots matching any keyword of the trapdoor, evaluating every slot.
+    pub fn count_matches(&self, ct: &Ciphertext<E>) -> Result<usize, PECDKError<E>> {
+        let (t1s, neg_t2s) = self.prepare_points();
+        let matches = (0..ct.c_points.len())
             .into_par_iter()
-            .zip(test2_invs)
-            .map(|(test1, test2_inv)| {
-                let mut val = test1;
-                val.mul_assign(&test2_inv);
-                E::final_exponentiation(&val).unwrap()
+            .map(|i| self.match_slot(&t1s, &neg_t2s, ct, i, &[]))
+            .collect::<Result<Vec<bool>, PECDKError<E>>>()?;
+        Ok(matches.into_iter().filter(|is_match| *is_match).count())
+    }
+
+    fn match_slot(
+        &self,
+        t1s: &[G1Prepared<E>],
+        neg_t2s: &[G1Prepared<E>],
+        ct: &Ciphertext<E>,
+        i: usize,
+        label: &[u8],
+    ) -> Result<bool, PECDKError<E>> {
+        let test_scalar = self.test_slot(t1s, neg_t2s, ct, i)?;
+        let hashed = hash_labeled_field2bytes::<E>(label, test_scalar)?;
+        Ok(digest_eq(&hashed, &ct.d_bytes[i]))
+    }
+
+    /// Computes prod_j e(t1_j, A_ij + C_i^t3) / prod_j e(t2_j, B_ij)
+    /// with one multi-Miller loop and one final exponentiation.
+    fn test_slot(
+        &self,
+        t1s: &[G1Prepared<E>],
+        neg_t2s: &[G1Prepared<E>],
+        ct: &Ciphertext<E>,
+        i: usize,
+    ) -> Result<E::Fqk, PECDKError<E>> {
+        // A trapdoor for m keywords has m + 1 points, and the ciphertext points beyond them
+        // would only be paired with zero coefficients, so a slot may be longer but not shorter.
+        let m = t1s.len();
+        if neg_t2s.len() != m {
+            return Err(PECDKError::PointCountMismatch(neg_t2s.len(), m));
+        }
+        for points in [ct.a_points[i].len(), ct.b_points[i].len()] {
+            if points < m {
+                return Err(PECDKError::PointCountMismatch(points, m));
+            }
+        }
+        let c_powed = ct.c_points[i].mul(self.t3);
+        let g2s = ct.a_points[i][..m]
+            .iter()
+            .map(|a_point| {
+                let mut point2 = a_point.into_projective();
+                point2.add_assign(&c_powed);
+                point2.into_affine().prepare()
             })
-            .collect::<Vec<E::Fqk>>();
-        match self.sym {
-            SearchSym::AND => {
-                let mut sum_valid = 0;
-                for i in 0..n {
-                    let left = &hash_field2bytes::<E>(test_scalars[i])?;
-                    let right = &ct.d_bytes[i];
-                    if left == right {
-                        sum_valid += 1;
-                    }
-                }
-                Ok(sum_valid == m)
+            .chain(ct.b_points[i][..m].iter().map(|b_point| b_point.prepare()))
+            .collect::<Vec<_>>();
+        let pairs = t1s
+            .iter()
+            .chain(neg_t2s.iter())
+            .zip(g2s.iter())
+            .collect::<Vec<_>>();
+        let val = E::miller_loop(pairs.iter());
+        E::final_exponentiation(&val).ok_or(PECDKError::FinalExponentiationError(val))
+    }
+}
+
+impl<E: EngineId> SecretKey<E> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WireError> {
+        let mut out = Vec::new();
+        write_header::<E>(
+            &mut out,
+            SchemeId::Pecdk,
+            ObjectKind::SecretKey,
+            self.num_keyword(),
+        )?;
+        for alpha in self.alphas.iter() {
+            write_fr(&mut out, alpha)?;
+        }
+        for beta in self.betas.iter() {
+            write_fr(&mut out, beta)?;
+        }
+        write_fr(&mut out, &self.theta)?;
+        write_point(&mut out, &self.g1);
+        write_point(&mut out, &self.g2);
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut reader = bytes;
+        let n = read_header::<E>(&mut reader, SchemeId::Pecdk, ObjectKind::SecretKey)?;
+        let alphas = read_frs(&mut reader, n + 1)?;
+        let betas = read_frs(&mut reader, n + 1)?;
+        let theta = read_fr(&mut reader)?;
+        let g1 = read_point(&mut reader)?;
+        let g2 = read_point(&mut reader)?;
+        finish(reader)?;
+        Ok(Self {
+            alphas,
+            betas,
+            theta,
+            g1,
+            g2,
+        })
+    }
+}
+
+impl<E: EngineId> PublicKey<E> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WireError> {
+        let mut out = Vec::new();
+        write_header::<E>(
+            &mut out,
+            SchemeId::Pecdk,
+            ObjectKind::PublicKey,
+            self.num_keyword(),
+        )?;
+        write_point(&mut out, &self.g2);
+        for point in self.x_points.iter().chain(self.y_points.iter()) {
+            write_point(&mut out, point);
+        }
+        write_point(&mut out, &self.z_point);
+        write_fqk::<E>(&mut out, self.mue)?;
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut reader = bytes;
+        let n = read_header::<E>(&mut reader, SchemeId::Pecdk, ObjectKind::PublicKey)?;
+        let g2 = read_point(&mut reader)?;
+        let x_points = read_points(&mut reader, n + 1)?;
+        let y_points = read_points(&mut reader, n + 1)?;
+        let z_point = read_point(&mut reader)?;
+        let mue = read_fqk::<E>(&mut reader)?;
+        finish(reader)?;
+        Ok(Self {
+            g2,
+            x_points,
+            y_points,
+            z_point,
+            mue,
+        })
+    }
+}
+
+impl<E: EngineId> Ciphertext<E> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WireError> {
+        let n = self.c_points.len();
+        check_len("a_points", self.a_points.len(), n)?;
+        check_len("b_points", self.b_points.len(), n)?;
+        check_len("d_bytes", self.d_bytes.len(), n)?;
+        for (a_points, b_points) in self.a_points.iter().zip(self.b_points.iter()) {
+            check_len("a_points slot", a_points.len(), n + 1)?;
+            check_len("b_points slot", b_points.len(), n + 1)?;
+        }
+        let mut out = Vec::new();
+        write_header::<E>(&mut out, SchemeId::Pecdk, ObjectKind::Ciphertext, n)?;
+        for i in 0..n {
+            for point in self.a_points[i].iter().chain(self.b_points[i].iter()) {
+                write_point(&mut out, point);
             }
-            SearchSym::OR => {
-                for i in 0..n {
-                    let left = &hash_field2bytes::<E>(test_scalars[i])?;
-                    let right = &ct.d_bytes[i];
-                    if left == right {
-                        return Ok(true);
-                    }
-                }
-                Ok(false)
+        }
+        for point in self.c_points.iter() {
+            write_point(&mut out, point);
+        }
+        for bytes in self.d_bytes.iter() {
+            write_bytes(&mut out, bytes)?;
+        }
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut reader = bytes;
+        let n = read_header::<E>(&mut reader, SchemeId::Pecdk, ObjectKind::Ciphertext)?;
+        let mut a_points = Vec::new();
+        let mut b_points = Vec::new();
+        for _ in 0..n {
+            a_points.push(read_points(&mut reader, n + 1)?);
+            b_points.push(read_points(&mut reader, n + 1)?);
+        }
+        let c_points = read_points(&mut reader, n)?;
+        let d_bytes = (0..n)
+            .map(|_| read_bytes(&mut reader))
+            .collect::<Result<Vec<Vec<u8>>, WireError>>()?;
+        finish(reader)?;
+        Ok(Self {
+            a_points,
+            b_points,
+            c_points,
+            d_bytes,
+        })
+    }
+}
+
+impl<E: EngineId> Trapdoor<E> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WireError> {
+        let m = self
+            .t1s
+            .len()
+            .checked_sub(1)
+            .ok_or(WireError::LengthMismatch("t1s", 0, 1))?;
+        check_len("t2s", self.t2s.len(), m + 1)?;
+        let mut out = Vec::new();
+        write_header::<E>(&mut out, SchemeId::Pecdk, ObjectKind::Trapdoor, m)?;
+        self.sym.write(&mut out)?;
+        for point in self.t1s.iter().chain(self.t2s.iter()) {
+            write_point(&mut out, point);
+        }
+        write_fr(&mut out, &self.t3)?;
+        Ok(out)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        let mut reader = bytes;
+        let m = read_header::<E>(&mut reader, SchemeId::Pecdk, ObjectKind::Trapdoor)?;
+        let sym = SearchSym::read(&mut reader)?;
+        let t1s = read_points(&mut reader, m + 1)?;
+        let t2s = read_points(&mut reader, m + 1)?;
+        let t3 = read_fr(&mut reader)?;
+        finish(reader)?;
+        Ok(Self { t1s, t2s, t3, sym })
+    }
+}
+
+impl SearchSym {
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), WireError> {
+        match self {
+            SearchSym::AND => out.push(0),
+            SearchSym::OR => out.push(1),
+            SearchSym::Threshold(k) => {
+                out.push(2);
+                write_len(out, *k)?;
             }
         }
+        Ok(())
+    }
+
+    fn read(reader: &mut &[u8]) -> Result<Self, WireError> {
+        match read_u8(reader)? {
+            0 => Ok(SearchSym::AND),
+            1 => Ok(SearchSym::OR),
+            2 => Ok(SearchSym::Threshold(read_len(reader)?)),
+            tag => Err(WireError::InvalidTag(tag)),
+        }
     }
 }
 
@@ -334,6 +674,7 @@ mod test {
     use rand::{thread_rng, Rng};
     use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
+    use sha2::{Digest, Sha256};
 
     #[test]
     fn test_pecdk_valid_case_and() {
@@ -482,6 +823,190 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_pecdk_fewer_keywords() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 8;
+        let keywords = (0..3u8).map(|i| vec![i; 4]).collect::<Vec<Vec<u8>>>();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords.clone(), &mut rng)
+            .unwrap();
+        assert_eq!(ct.c_points.len(), n);
+
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(keywords.clone(), SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(trapdoor.test(&ct).unwrap());
+
+        let mut invalid_keywords = keywords.clone();
+        invalid_keywords.push(vec![3u8; 4]);
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(invalid_keywords, SearchSym::AND, &mut rng)
+            .unwrap();
+        assert!(!trapdoor.test(&ct).unwrap());
+
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(vec![vec![3u8; 4], vec![]], SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(!trapdoor.test(&ct).unwrap());
+
+        let ct = public_key.encrypt::<_, Fr>(vec![], &mut rng).unwrap();
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(keywords, SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(!trapdoor.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_pecdk_threshold() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 8;
+        let keywords = (0..8u8).map(|i| vec![i; 4]).collect::<Vec<Vec<u8>>>();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords[0..4].to_vec(), &mut rng)
+            .unwrap();
+
+        // Two of the five indicators are absent from the ciphertext.
+        let indicators = keywords[1..6].to_vec();
+        for (k, expected) in [(1, true), (3, true), (4, false), (5, false)] {
+            let trapdoor = secret_key
+                .gen_trapdoor::<_, Fr>(indicators.clone(), SearchSym::Threshold(k), &mut rng)
+                .unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(indicators.clone(), SearchSym::Threshold(3), &mut rng)
+            .unwrap();
+        let decoded = Trapdoor::<Bls12>::from_bytes(&trapdoor.to_bytes().unwrap()).unwrap();
+        assert!(matches!(decoded.sym, SearchSym::Threshold(3)));
+        assert!(decoded.test(&ct).unwrap());
+
+        for k in [0, 6] {
+            assert!(matches!(
+                secret_key.gen_trapdoor::<_, Fr>(
+                    indicators.clone(),
+                    SearchSym::Threshold(k),
+                    &mut rng
+                ),
+                Err(PECDKError::InvalidThreshold(_, 5))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pecdk_secret_key_debug_redacted() {
+        let secret_key = SecretKey::<Bls12>::from_seed(&[7u8; 32], 2);
+        let debug = format!("{:?}", secret_key);
+        assert_eq!(debug, "SecretKey { num_keyword: 2, .. }");
+        assert!(!debug.contains(&secret_key.theta.to_string()));
+    }
+
+    #[test]
+    fn test_pecdk_from_seed_kat() {
+        let secret_key = SecretKey::<Bls12>::from_seed(&[7u8; 32], 2);
+        let public_key = secret_key.into_public_key();
+        let digest = <Sha256 as Digest>::digest(&public_key.to_bytes().unwrap());
+        assert_eq!(
+            digest.to_vec(),
+            vec![
+                160, 53, 35, 194, 208, 253, 198, 239, 117, 243, 188, 152, 51, 90, 159, 84, 102,
+                249, 130, 38, 225, 157, 154, 24, 192, 105, 251, 36, 226, 42, 102, 0
+            ]
+        );
+        assert_eq!(
+            secret_key.to_bytes().unwrap(),
+            SecretKey::<Bls12>::from_seed(&[7u8; 32], 2)
+                .to_bytes()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pecdk_count_matches() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 6;
+        let keywords = (0..6u8).map(|i| vec![i; 4]).collect::<Vec<Vec<u8>>>();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords[0..3].to_vec(), &mut rng)
+            .unwrap();
+        for (sym, expected) in [
+            (SearchSym::AND, false),
+            (SearchSym::OR, true),
+            (SearchSym::Threshold(2), true),
+            (SearchSym::Threshold(3), false),
+        ] {
+            let trapdoor = secret_key
+                .gen_trapdoor::<_, Fr>(keywords[1..4].to_vec(), sym, &mut rng)
+                .unwrap();
+            assert_eq!(trapdoor.count_matches(&ct).unwrap(), 2);
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_pecdk_excessive_keywords() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 2;
+        let keywords = (0..3u8).map(|i| vec![i; 4]).collect::<Vec<Vec<u8>>>();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        assert!(matches!(
+            public_key.encrypt::<_, Fr>(keywords.clone(), &mut rng),
+            Err(PECDKError::ExcessiveNumberOfKeywords(3, 2))
+        ));
+        assert!(matches!(
+            secret_key.gen_trapdoor::<_, Fr>(keywords, SearchSym::OR, &mut rng),
+            Err(PECDKError::ExcessiveNumberOfKeywords(3, 2))
+        ));
+    }
+
+    #[test]
+    fn test_pecdk_point_count_mismatch() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let keywords = (0..3u8).map(|i| vec![i; 4]).collect::<Vec<Vec<u8>>>();
+        let small_key = SecretKey::<Bls12>::gen(&mut rng, 2);
+        let ct = small_key
+            .into_public_key()
+            .encrypt::<_, Fr>(keywords[0..2].to_vec(), &mut rng)
+            .unwrap();
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, 3);
+        let mut trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(keywords, SearchSym::OR, &mut rng)
+            .unwrap();
+        assert!(matches!(
+            trapdoor.test(&ct),
+            Err(PECDKError::PointCountMismatch(3, 4))
+        ));
+
+        trapdoor.t2s.pop();
+        assert!(matches!(
+            trapdoor.count_matches(&ct),
+            Err(PECDKError::PointCountMismatch(3, 4))
+        ));
+    }
+
     fn test_generic<R: RngCore>(
         ct_keywords: Vec<Vec<u8>>,
         td_keywords: Vec<Vec<u8>>,
@@ -529,6 +1054,146 @@ mod test {
         //let td_json = serde_json::to_string(&trapdoor).unwrap();
         //println!("td_json {}", td_json.len());
     }
+
+    #[test]
+    fn test_pecdk_bytes_roundtrip() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 8;
+        let mut thread_rng = thread_rng();
+        let mut keywords = Vec::with_capacity(n);
+        for _ in 0..n {
+            let keyword = (0..16).map(|_| thread_rng.gen()).collect::<Vec<u8>>();
+            keywords.push(keyword);
+        }
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let sk_bytes = secret_key.to_bytes().unwrap();
+        let pk_bytes = public_key.to_bytes().unwrap();
+        let secret_key = SecretKey::<Bls12>::from_bytes(&sk_bytes).unwrap();
+        let public_key = PublicKey::<Bls12>::from_bytes(&pk_bytes).unwrap();
+        assert_eq!(secret_key.to_bytes().unwrap(), sk_bytes);
+        assert_eq!(public_key.to_bytes().unwrap(), pk_bytes);
+
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords.clone(), &mut rng)
+            .unwrap();
+        let ct_bytes = ct.to_bytes().unwrap();
+        let ct_json = serde_json::to_string(&ct).unwrap();
+        assert!(ct_bytes.len() < ct_json.len());
+        let ct = Ciphertext::<Bls12>::from_bytes(&ct_bytes).unwrap();
+        assert_eq!(ct.to_bytes().unwrap(), ct_bytes);
+
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(keywords[0..2].to_vec(), SearchSym::AND, &mut rng)
+            .unwrap();
+        let td_bytes = trapdoor.to_bytes().unwrap();
+        let trapdoor = Trapdoor::<Bls12>::from_bytes(&td_bytes).unwrap();
+        assert_eq!(trapdoor.to_bytes().unwrap(), td_bytes);
+        assert!(trapdoor.test(&ct).unwrap());
+    }
+
+    #[test]
+    fn test_pecdk_serde_borrowed() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4;
+        let mut thread_rng = thread_rng();
+        let mut keywords = Vec::with_capacity(n);
+        for _ in 0..n {
+            let keyword = (0..16).map(|_| thread_rng.gen()).collect::<Vec<u8>>();
+            keywords.push(keyword);
+        }
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords.clone(), &mut rng)
+            .unwrap();
+        let trapdoor = secret_key
+            .gen_trapdoor::<_, Fr>(keywords[0..2].to_vec(), SearchSym::AND, &mut rng)
+            .unwrap();
+        let ct_bytes = ct.to_bytes().unwrap();
+        let td_bytes = trapdoor.to_bytes().unwrap();
+
+        let ct_json = serde_json::to_string(&ct).unwrap();
+        let td_json = serde_json::to_string(&trapdoor).unwrap();
+        let ct_from_json: Ciphertext<Bls12> = serde_json::from_str(&ct_json).unwrap();
+        let td_from_json: Trapdoor<Bls12> = serde_json::from_str(&td_json).unwrap();
+        assert_eq!(ct_from_json.to_bytes().unwrap(), ct_bytes);
+        assert_eq!(td_from_json.to_bytes().unwrap(), td_bytes);
+
+        let ct_slice = serde_json::to_vec(&ct).unwrap();
+        let td_slice = serde_json::to_vec(&trapdoor).unwrap();
+        let ct_from_slice = from_borrowed_json::<Ciphertext<Bls12>>(&ct_slice[..]);
+        let td_from_slice = from_borrowed_json::<Trapdoor<Bls12>>(&td_slice[..]);
+        assert_eq!(ct_from_slice.to_bytes().unwrap(), ct_bytes);
+        assert_eq!(td_from_slice.to_bytes().unwrap(), td_bytes);
+
+        let ct_bincode = bincode::serialize(&ct).unwrap();
+        let td_bincode = bincode::serialize(&trapdoor).unwrap();
+        let ct_from_bincode: Ciphertext<Bls12> = bincode::deserialize(&ct_bincode).unwrap();
+        let td_from_bincode: Trapdoor<Bls12> = bincode::deserialize(&td_bincode).unwrap();
+        assert_eq!(ct_from_bincode.to_bytes().unwrap(), ct_bytes);
+        assert_eq!(td_from_bincode.to_bytes().unwrap(), td_bytes);
+        assert!(td_from_bincode.test(&ct_from_json).unwrap());
+    }
+
+    fn from_borrowed_json<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> T {
+        serde_json::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_pecdk_bytes_invalid() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, 2);
+        let sk_bytes = secret_key.to_bytes().unwrap();
+        assert!(matches!(
+            PublicKey::<Bls12>::from_bytes(&sk_bytes),
+            Err(WireError::KindMismatch(1, 2))
+        ));
+        assert!(matches!(
+            SecretKey::<Bls12>::from_bytes(&sk_bytes[1..]),
+            Err(WireError::InvalidMagic)
+        ));
+        assert!(matches!(
+            SecretKey::<Bls12>::from_bytes(&sk_bytes[0..sk_bytes.len() - 1]),
+            Err(WireError::Io(_))
+        ));
+        let mut extended = sk_bytes.clone();
+        extended.push(0);
+        assert!(matches!(
+            SecretKey::<Bls12>::from_bytes(&extended),
+            Err(WireError::TrailingBytes(1))
+        ));
+        let mut huge_count = sk_bytes;
+        huge_count[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            SecretKey::<Bls12>::from_bytes(&huge_count),
+            Err(WireError::CountOutOfRange(u32::MAX, _))
+        ));
+
+        let public_key = secret_key.into_public_key();
+        let mut ct = public_key
+            .encrypt::<_, Fr>(vec![b"a".to_vec()], &mut rng)
+            .unwrap();
+        ct.a_points[1].pop();
+        assert!(matches!(
+            ct.to_bytes(),
+            Err(WireError::LengthMismatch("a_points slot", 2, 3))
+        ));
+        ct.b_points.pop();
+        assert!(matches!(
+            ct.to_bytes(),
+            Err(WireError::LengthMismatch("b_points", 1, 2))
+        ));
+    }
 }
 
 // n=128 (bytes)
@@ -0,0 +1,108 @@
+use super::*;
+use serde::{Deserializer, Serializer};
+use std::fmt;
+
+/// A `Trapdoor` whose G1 points are prepared for the Miller loop once.
+/// It is serialized as the underlying trapdoor and prepared again when deserialized.
+#[derive(Clone)]
+pub struct PreparedTrapdoor<E: Engine> {
+    trapdoor: Trapdoor<E>,
+    t1s: Vec<G1Prepared<E>>,
+    neg_t2s: Vec<G1Prepared<E>>,
+}
+
+impl<E: Engine> Trapdoor<E> {
+    pub fn prepare(&self) -> PreparedTrapdoor<E> {
+        PreparedTrapdoor::from(self.clone())
+    }
+}
+
+impl<E: Engine> From<Trapdoor<E>> for PreparedTrapdoor<E> {
+    fn from(trapdoor: Trapdoor<E>) -> Self {
+        let (t1s, neg_t2s) = trapdoor.prepare_points();
+        Self {
+            trapdoor,
+            t1s,
+            neg_t2s,
+        }
+    }
+}
+
+impl<E: Engine> PreparedTrapdoor<E> {
+    pub fn trapdoor(&self) -> &Trapdoor<E> {
+        &self.trapdoor
+    }
+
+    pub fn into_trapdoor(self) -> Trapdoor<E> {
+        self.trapdoor
+    }
+
+    pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PECDKError<E>> {
+        self.trapdoor
+            .test_with_prepared(&self.t1s, &self.neg_t2s, ct, &[])
+    }
+}
+
+impl<E: Engine + fmt::Debug> fmt::Debug for PreparedTrapdoor<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedTrapdoor")
+            .field("trapdoor", &self.trapdoor)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Serialize for PreparedTrapdoor<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.trapdoor.serialize(serializer)
+    }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for PreparedTrapdoor<E>
+where
+    E::G1Affine: Deserialize<'de>,
+    E::Fr: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Trapdoor::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_pecdk_prepared() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let keywords = (0..4u8).map(|i| vec![i; 2]).collect::<Vec<Vec<u8>>>();
+        let ct = public_key
+            .encrypt::<_, Fr>(keywords[0..2].to_vec(), &mut rng)
+            .unwrap();
+        for (td_keywords, sym) in [
+            (keywords[0..2].to_vec(), SearchSym::AND),
+            (keywords[1..3].to_vec(), SearchSym::AND),
+            (keywords[1..3].to_vec(), SearchSym::OR),
+            (keywords[2..4].to_vec(), SearchSym::OR),
+        ] {
+            let trapdoor = secret_key
+                .gen_trapdoor::<_, Fr>(td_keywords, sym, &mut rng)
+                .unwrap();
+            let prepared = trapdoor.prepare();
+            assert_eq!(prepared.test(&ct).unwrap(), trapdoor.test(&ct).unwrap());
+
+            let json = serde_json::to_string(&prepared).unwrap();
+            assert_eq!(json, serde_json::to_string(&trapdoor).unwrap());
+            let decoded: PreparedTrapdoor<Bls12> = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.test(&ct).unwrap(), trapdoor.test(&ct).unwrap());
+        }
+    }
+}
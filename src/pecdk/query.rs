@@ -0,0 +1,220 @@
+use super::*;
+
+/// A boolean formula over keywords.
+/// An empty `And` is always true and an empty `Or` is always false.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Query {
+    Keyword(Vec<u8>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+impl Query {
+    pub fn keyword<K: Into<Vec<u8>>>(keyword: K) -> Self {
+        Query::Keyword(keyword.into())
+    }
+
+    pub fn and(children: Vec<Query>) -> Self {
+        Query::And(children)
+    }
+
+    pub fn or(children: Vec<Query>) -> Self {
+        Query::Or(children)
+    }
+
+    pub fn negate(child: Query) -> Self {
+        Query::Not(Box::new(child))
+    }
+}
+
+/// A compiled `Query`.
+/// The keyword children of each AND/OR node are merged into one `Trapdoor`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "",
+    deserialize = "E::G1Affine: Deserialize<'de>, E::Fr: Deserialize<'de>"
+))]
+pub enum QueryTrapdoor<E: Engine> {
+    Leaf(Trapdoor<E>),
+    And(Option<Trapdoor<E>>, Vec<QueryTrapdoor<E>>),
+    Or(Option<Trapdoor<E>>, Vec<QueryTrapdoor<E>>),
+    Not(Box<QueryTrapdoor<E>>),
+}
+
+type QueryChildren<E> = (Option<Trapdoor<E>>, Vec<QueryTrapdoor<E>>);
+
+impl<E: Engine> SecretKey<E> {
+    pub fn gen_query_trapdoor<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        query: &Query,
+        rng: &mut R,
+    ) -> Result<QueryTrapdoor<E>, PECDKError<E>> {
+        match query {
+            Query::Keyword(keyword) => Ok(QueryTrapdoor::Leaf(self.gen_trapdoor::<R, F>(
+                vec![keyword.clone()],
+                SearchSym::AND,
+                rng,
+            )?)),
+            Query::And(children) => {
+                let (leaf, nodes) =
+                    self.gen_query_children::<R, F>(children, SearchSym::AND, rng)?;
+                Ok(QueryTrapdoor::And(leaf, nodes))
+            }
+            Query::Or(children) => {
+                let (leaf, nodes) =
+                    self.gen_query_children::<R, F>(children, SearchSym::OR, rng)?;
+                Ok(QueryTrapdoor::Or(leaf, nodes))
+            }
+            Query::Not(child) => Ok(QueryTrapdoor::Not(Box::new(
+                self.gen_query_trapdoor::<R, F>(child, rng)?,
+            ))),
+        }
+    }
+
+    fn gen_query_children<R: RngCore, F: BaseROFr<E>>(
+        &self,
+        children: &[Query],
+        sym: SearchSym,
+        rng: &mut R,
+    ) -> Result<QueryChildren<E>, PECDKError<E>> {
+        let mut keywords = Vec::new();
+        let mut nodes = Vec::new();
+        for child in children {
+            match child {
+                Query::Keyword(keyword) => keywords.push(keyword.clone()),
+                _ => nodes.push(self.gen_query_trapdoor::<R, F>(child, rng)?),
+            }
+        }
+        // A repeated keyword would count once in the ciphertext but twice in an AND trapdoor.
+        keywords.sort();
+        keywords.dedup();
+        let leaf = if keywords.is_empty() {
+            None
+        } else {
+            Some(self.gen_trapdoor::<R, F>(keywords, sym, rng)?)
+        };
+        Ok((leaf, nodes))
+    }
+}
+
+impl<E: Engine> QueryTrapdoor<E> {
+    pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PECDKError<E>> {
+        match self {
+            QueryTrapdoor::Leaf(trapdoor) => trapdoor.test(ct),
+            QueryTrapdoor::And(leaf, nodes) => {
+                if let Some(leaf) = leaf {
+                    if !leaf.test(ct)? {
+                        return Ok(false);
+                    }
+                }
+                for node in nodes {
+                    if !node.test(ct)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            QueryTrapdoor::Or(leaf, nodes) => {
+                if let Some(leaf) = leaf {
+                    if leaf.test(ct)? {
+                        return Ok(true);
+                    }
+                }
+                for node in nodes {
+                    if node.test(ct)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            QueryTrapdoor::Not(node) => Ok(!node.test(ct)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::{Bls12, Fr};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_query_dnf() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 3;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        let query = Query::or(vec![
+            Query::and(vec![
+                Query::keyword("region=EU"),
+                Query::keyword("status=open"),
+                Query::keyword("region=EU"),
+            ]),
+            Query::keyword("priority=high"),
+        ]);
+        let trapdoor = secret_key
+            .gen_query_trapdoor::<_, Fr>(&query, &mut rng)
+            .unwrap();
+        let cases = [
+            (vec!["region=EU", "status=open", "priority=low"], true),
+            (vec!["region=US", "status=open", "priority=high"], true),
+            (vec!["region=EU", "status=closed", "priority=low"], false),
+            (vec!["region=US", "status=open"], false),
+        ];
+        for (keywords, expected) in cases {
+            let keywords = keywords
+                .into_iter()
+                .map(|keyword| keyword.as_bytes().to_vec())
+                .collect();
+            let ct = public_key.encrypt::<_, Fr>(keywords, &mut rng).unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_query_cnf_and_not() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 3;
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng, n);
+        let public_key = secret_key.into_public_key();
+        // (A OR B) AND A AND (C OR D) AND NOT E
+        let query = Query::and(vec![
+            Query::or(vec![Query::keyword("A"), Query::keyword("B")]),
+            Query::keyword("A"),
+            Query::or(vec![Query::keyword("C"), Query::keyword("D")]),
+            Query::negate(Query::keyword("E")),
+        ]);
+        let trapdoor = secret_key
+            .gen_query_trapdoor::<_, Fr>(&query, &mut rng)
+            .unwrap();
+        let cases = [
+            (vec!["A", "D"], true),
+            (vec!["A", "C", "E"], false),
+            (vec!["A", "B"], false),
+            (vec!["B", "C"], false),
+        ];
+        for (keywords, expected) in cases {
+            let keywords = keywords
+                .into_iter()
+                .map(|keyword| keyword.as_bytes().to_vec())
+                .collect();
+            let ct = public_key.encrypt::<_, Fr>(keywords, &mut rng).unwrap();
+            assert_eq!(trapdoor.test(&ct).unwrap(), expected);
+        }
+
+        let json = serde_json::to_string(&trapdoor).unwrap();
+        let decoded: QueryTrapdoor<Bls12> = serde_json::from_str(&json).unwrap();
+        let ct = public_key
+            .encrypt::<_, Fr>(vec![b"A".to_vec(), b"C".to_vec()], &mut rng)
+            .unwrap();
+        assert!(decoded.test(&ct).unwrap());
+    }
+}
@@ -1,159 +1,150 @@
 use crate::c_utils::*;
+use crate::curve::CurveEngine;
 use crate::peks::*;
-use errno::{set_errno, Errno};
-use paired::bls12_381::Bls12;
+use crate::seed::CSeed;
 use rand_core::OsRng;
 use std::os::raw::c_char;
-use std::os::raw::c_int;
 
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct CPeksSecretKey {
-    ptr: *mut c_char,
+    pub(crate) ptr: *mut c_char,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct CPeksPublicKey {
-    ptr: *mut c_char,
+    pub(crate) ptr: *mut c_char,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct CPeksCiphertext {
-    ptr: *mut c_char,
+    pub(crate) ptr: *mut c_char,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct CPeksTrapdoor {
-    ptr: *mut c_char,
+    pub(crate) ptr: *mut c_char,
 }
 
-const EINVAL: i32 = 22;
+#[no_mangle]
+pub extern "C" fn peksGenSecretKey(secret_key: *mut CPeksSecretKey) -> CErrorCode {
+    ffi_output("peksGenSecretKey", secret_key, || {
+        let mut rng = OsRng;
+        let sk = SecretKey::<CurveEngine>::gen(&mut rng);
+        Ok(CPeksSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn peksGenSecretKeyFromSeed(
+    seed: CSeed,
+    secret_key: *mut CPeksSecretKey,
+) -> CErrorCode {
+    ffi_output("peksGenSecretKeyFromSeed", secret_key, || {
+        let sk = SecretKey::<CurveEngine>::from_seed(&seed.bytes);
+        Ok(CPeksSecretKey {
+            ptr: to_secret_json_ptr(&sk)?,
+        })
+    })
+}
 
+/// Writes the public key paired with `peksGenSecretKeyFromSeed` for the same seed.
 #[no_mangle]
-pub extern "C" fn peks_gen_secret_key() -> CPeksSecretKey {
-    let mut rng = OsRng;
-    let sk = SecretKey::<Bls12>::gen(&mut rng);
-    let sk_str = serde_json::to_string(&sk)
-        .expect("Fail to convert a secret key to a string in peks_gen_secret_key");
-    CPeksSecretKey {
-        ptr: str2ptr(sk_str),
-    }
+pub extern "C" fn peksGenPublicKeyFromSeed(
+    seed: CSeed,
+    public_key: *mut CPeksPublicKey,
+) -> CErrorCode {
+    ffi_output("peksGenPublicKeyFromSeed", public_key, || {
+        let (_, pk) = SecretKey::<CurveEngine>::key_pair_from_seed(&seed.bytes);
+        Ok(CPeksPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn peks_gen_public_key(secret_key: &CPeksSecretKey) -> CPeksPublicKey {
-    let mut rng = OsRng;
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPeksPublicKey {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let pk = sk.into_public_key(&mut rng);
-    let pk_str = serde_json::to_string(&pk)
-        .expect("Fail to convert a public key to a string in peks_gen_public_key");
-    CPeksPublicKey {
-        ptr: str2ptr(pk_str),
-    }
+pub extern "C" fn peksGenPublicKey(
+    secret_key: CPeksSecretKey,
+    public_key: *mut CPeksPublicKey,
+) -> CErrorCode {
+    ffi_output("peksGenPublicKey", public_key, || {
+        let mut rng = OsRng;
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let pk = sk.into_public_key(&mut rng);
+        Ok(CPeksPublicKey {
+            ptr: to_json_ptr(&pk)?,
+        })
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn peks_encrypt_keyword(
-    public_key: &CPeksPublicKey,
+pub extern "C" fn peksEncryptKeyword(
+    public_key: CPeksPublicKey,
     keyword: *mut c_char,
-) -> CPeksCiphertext {
-    let mut rng = OsRng;
-    let pk = match serde_json::from_str::<PublicKey<Bls12>>(&ptr2str(public_key.ptr)) {
-        Ok(pk) => pk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPeksCiphertext {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let keyword = ptr2str(keyword).as_bytes();
-    let ct = pk
-        .encrypt(keyword, &mut rng)
-        .expect("Fail to generate a ciphertext in peks_encrypt_keyword");
-    let ct_str = serde_json::to_string(&ct)
-        .expect("Fail to convert a ciphertext to a string in peks_encrypt_keyword");
-    CPeksCiphertext {
-        ptr: str2ptr(ct_str),
-    }
+    ciphertext: *mut CPeksCiphertext,
+) -> CErrorCode {
+    ffi_output("peksEncryptKeyword", ciphertext, || {
+        let mut rng = OsRng;
+        let pk = from_json_ptr::<PublicKey<CurveEngine>>(public_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let ct = pk.encrypt(keyword, &mut rng).map_err(CError::crypto)?;
+        Ok(CPeksCiphertext {
+            ptr: to_json_ptr(&ct)?,
+        })
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn peks_gen_trapdoor(
-    secret_key: &CPeksSecretKey,
+pub extern "C" fn peksGenTrapdoor(
+    secret_key: CPeksSecretKey,
     keyword: *mut c_char,
-) -> CPeksTrapdoor {
-    let sk = match serde_json::from_str::<SecretKey<Bls12>>(&ptr2str(secret_key.ptr)) {
-        Ok(sk) => sk,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return CPeksTrapdoor {
-                ptr: str2ptr(String::new()),
-            };
-        }
-    };
-    let keyword = ptr2str(keyword).as_bytes();
-    let td = sk.gen_trapdoor(keyword);
-    let td_str = serde_json::to_string(&td)
-        .expect("Fail to convert a trapdoor to a string in peks_gen_trapdoor");
-    CPeksTrapdoor {
-        ptr: str2ptr(td_str),
-    }
+    trapdoor: *mut CPeksTrapdoor,
+) -> CErrorCode {
+    ffi_output("peksGenTrapdoor", trapdoor, || {
+        let sk = from_json_ptr::<SecretKey<CurveEngine>>(secret_key.ptr)?;
+        let keyword = ptr2str(keyword)?.as_bytes();
+        let td = sk.gen_trapdoor(keyword);
+        Ok(CPeksTrapdoor {
+            ptr: to_json_ptr(&td)?,
+        })
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn peks_test(ciphertext: CPeksCiphertext, trapdoor: CPeksTrapdoor) -> c_int {
-    let ct = match serde_json::from_str::<Ciphertext<Bls12>>(&ptr2str(ciphertext.ptr)) {
-        Ok(ct) => ct,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return -1;
-        }
-    };
-    let td = match serde_json::from_str::<Trapdoor<Bls12>>(&ptr2str(trapdoor.ptr)) {
-        Ok(td) => td,
-        Err(_) => {
-            set_errno(Errno(EINVAL));
-            return -1;
-        }
-    };
-    let tested = td
-        .test(&ct)
-        .expect("Fail to test the ciphertext with the trapdoor in peks_test");
-    if tested {
-        1
-    } else {
-        0
-    }
+pub extern "C" fn peksTest(
+    ciphertext: CPeksCiphertext,
+    trapdoor: CPeksTrapdoor,
+    result: *mut bool,
+) -> CErrorCode {
+    ffi_output("peksTest", result, || {
+        let ct = from_json_ptr::<Ciphertext<CurveEngine>>(ciphertext.ptr)?;
+        let td = from_json_ptr::<Trapdoor<CurveEngine>>(trapdoor.ptr)?;
+        td.test(&ct).map_err(CError::crypto)
+    })
 }
 
+/// Overwrites the serialized secret key with zeros before freeing it.
 #[no_mangle]
-pub extern "C" fn peks_free_secret_key(secret_key: CPeksSecretKey) {
-    drop_ptr(secret_key.ptr);
+pub extern "C" fn peksFreeSecretKey(secret_key: CPeksSecretKey) {
+    drop_secret_ptr(secret_key.ptr);
 }
 
 #[no_mangle]
-pub extern "C" fn peks_free_public_key(public_key: CPeksPublicKey) {
+pub extern "C" fn peksFreePublicKey(public_key: CPeksPublicKey) {
     drop_ptr(public_key.ptr);
 }
 
 #[no_mangle]
-pub extern "C" fn peks_free_ciphertext(ciphertext: CPeksCiphertext) {
+pub extern "C" fn peksFreeCiphertext(ciphertext: CPeksCiphertext) {
     drop_ptr(ciphertext.ptr);
 }
 
 #[no_mangle]
-pub extern "C" fn peks_free_trapdoor(trapdoor: CPeksTrapdoor) {
+pub extern "C" fn peksFreeTrapdoor(trapdoor: CPeksTrapdoor) {
     drop_ptr(trapdoor.ptr);
 }
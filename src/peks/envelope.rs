@@ -0,0 +1,102 @@
+use super::*;
+use crate::payload::SealedPayload;
+
+const ENVELOPE_INFO: &[u8] = b"peks_envelope";
+
+/// A searchable ciphertext with an attached AEAD-encrypted payload.
+/// The payload key is encapsulated as `g^r` with the shared secret `h^r = (g^r)^alpha`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "E::G2Affine: Deserialize<'de>"))]
+pub struct Envelope<E: Engine> {
+    ciphertext: Ciphertext<E>,
+    encapsulated_key: E::G2Affine,
+    payload: SealedPayload,
+}
+
+impl<E: Engine> PublicKey<E> {
+    pub fn encrypt_envelope<R: RngCore>(
+        &self,
+        keyword: &[u8],
+        payload: &[u8],
+        rng: &mut R,
+    ) -> Result<Envelope<E>, PEKSError> {
+        let ciphertext = self.encrypt(keyword, rng)?;
+        let r = <E::Fr as Field>::random(rng);
+        let encapsulated_key = self.g.mul(r).into_affine();
+        let shared = self.h.mul(r).into_affine().into_compressed();
+        let aad = envelope_aad(&ciphertext, &encapsulated_key);
+        let payload = SealedPayload::seal(shared.as_ref(), ENVELOPE_INFO, &aad, payload, rng)?;
+        Ok(Envelope {
+            ciphertext,
+            encapsulated_key,
+            payload,
+        })
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    pub fn open_envelope(&self, envelope: &Envelope<E>) -> Result<Vec<u8>, PEKSError> {
+        let shared = envelope
+            .encapsulated_key
+            .mul(self.alpha)
+            .into_affine()
+            .into_compressed();
+        let aad = envelope_aad(&envelope.ciphertext, &envelope.encapsulated_key);
+        Ok(envelope
+            .payload
+            .open(shared.as_ref(), ENVELOPE_INFO, &aad)?)
+    }
+}
+
+impl<E: Engine> Envelope<E> {
+    pub fn ciphertext(&self) -> &Ciphertext<E> {
+        &self.ciphertext
+    }
+
+    pub fn test(&self, trapdoor: &Trapdoor<E>) -> Result<bool, PEKSError> {
+        trapdoor.test(&self.ciphertext)
+    }
+}
+
+fn envelope_aad<E: Engine>(ct: &Ciphertext<E>, encapsulated_key: &E::G2Affine) -> Vec<u8> {
+    let mut aad = encapsulated_key.into_compressed().as_ref().to_vec();
+    aad.extend_from_slice(&ct.b);
+    aad
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use paired::bls12_381::Bls12;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_peks_envelope() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let secret_key = SecretKey::<Bls12>::gen(&mut rng);
+        let public_key = secret_key.into_public_key(&mut rng);
+        let envelope = public_key
+            .encrypt_envelope(b"keyword", b"record body", &mut rng)
+            .unwrap();
+        assert!(envelope.test(&secret_key.gen_trapdoor(b"keyword")).unwrap());
+        assert!(!envelope.test(&secret_key.gen_trapdoor(b"other")).unwrap());
+        assert_eq!(
+            secret_key.open_envelope(&envelope).unwrap(),
+            b"record body".to_vec()
+        );
+
+        let json = serde_json::to_string(&envelope).unwrap();
+        let decoded: Envelope<Bls12> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            secret_key.open_envelope(&decoded).unwrap(),
+            b"record body".to_vec()
+        );
+
+        let wrong_key = SecretKey::<Bls12>::gen(&mut rng);
+        assert!(wrong_key.open_envelope(&envelope).is_err());
+    }
+}
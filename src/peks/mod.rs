@@ -1,23 +1,35 @@
+#[cfg(feature = "c_api")]
+mod c_api;
+mod envelope;
+
 use crate::hashes::*;
+use crate::seed::{seed_rng, SEED_SIZE};
+use crate::wipe::wipe;
+use crate::PayloadError;
+
+#[cfg(feature = "c_api")]
+pub use c_api::*;
+pub use envelope::*;
+
 use fff::Field;
 use groupy::{CurveAffine, CurveProjective};
 use paired::Engine;
 use rand_core::RngCore;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use thiserror::Error;
 
-/*#[cfg(feature = "c_api")]
-mod c_api;
-#[cfg(feature = "c_api")]
-pub use c_api::*;*/
+const SEED_DOMAIN: &[u8] = b"peks_secret_key";
 
 #[derive(Error, Debug)]
 pub enum PEKSError {
     #[error(transparent)]
     ECHashError(#[from] ECHashError),
+    #[error(transparent)]
+    PayloadError(#[from] PayloadError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SecretKey<E: Engine> {
     alpha: E::Fr,
 }
@@ -45,6 +57,19 @@ impl<E: Engine> SecretKey<E> {
         Self { alpha }
     }
 
+    /// Derives the secret key deterministically from `seed`.
+    pub fn from_seed(seed: &[u8; SEED_SIZE]) -> Self {
+        Self::key_pair_from_seed(seed).0
+    }
+
+    /// Derives the secret key and a public key deterministically from `seed`.
+    pub fn key_pair_from_seed(seed: &[u8; SEED_SIZE]) -> (Self, PublicKey<E>) {
+        let mut rng = seed_rng(SEED_DOMAIN, seed);
+        let secret_key = Self::gen(&mut rng);
+        let public_key = secret_key.into_public_key(&mut rng);
+        (secret_key, public_key)
+    }
+
     pub fn into_public_key<R: RngCore>(&self, rng: &mut R) -> PublicKey<E> {
         let g = <E::G2 as CurveProjective>::random(rng).into_affine();
         let h = g.mul(self.alpha).into_affine();
@@ -58,6 +83,21 @@ impl<E: Engine> SecretKey<E> {
     }
 }
 
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        wipe(
+            std::slice::from_mut(&mut self.alpha),
+            <E::Fr as Field>::zero(),
+        );
+    }
+}
+
 impl<E: Engine> PublicKey<E> {
     pub fn from_secret_key<R: RngCore>(secret_key: &SecretKey<E>, rng: &mut R) -> Self {
         secret_key.into_public_key(rng)
@@ -83,7 +123,7 @@ impl<E: Engine> Trapdoor<E> {
     pub fn test(&self, ct: &Ciphertext<E>) -> Result<bool, PEKSError> {
         let pairinged = E::pairing(self.t.into_projective(), ct.a.into_projective());
         let hashed = hash_field2bytes::<E>(pairinged)?;
-        Ok(hashed == ct.b)
+        Ok(digest_eq(&hashed, &ct.b))
     }
 }
 
@@ -94,6 +134,7 @@ mod test {
     use rand::{thread_rng, Rng};
     use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
+    use sha2::{Digest, Sha256};
 
     #[test]
     fn test_peks_valid_case() {
@@ -116,4 +157,33 @@ mod test {
         assert_eq!(trapdoor.test(&ct1).unwrap(), true);
         assert_eq!(trapdoor.test(&ct2).unwrap(), false);
     }
+
+    #[test]
+    fn test_peks_from_seed_kat() {
+        let (secret_key, public_key) = SecretKey::<Bls12>::key_pair_from_seed(&[7u8; 32]);
+        let json = serde_json::to_string(&public_key).unwrap();
+        let digest = <Sha256 as Digest>::digest(json.as_bytes());
+        assert_eq!(
+            digest.to_vec(),
+            vec![
+                233, 176, 9, 72, 184, 80, 147, 136, 144, 125, 68, 93, 206, 28, 72, 197, 178, 120,
+                80, 187, 197, 78, 19, 190, 86, 103, 243, 176, 164, 185, 183, 215
+            ]
+        );
+        let ct = public_key.encrypt(b"keyword", &mut thread_rng()).unwrap();
+        let trapdoor = SecretKey::<Bls12>::from_seed(&[7u8; 32]).gen_trapdoor(b"keyword");
+        assert!(trapdoor.test(&ct).unwrap());
+        assert_eq!(
+            serde_json::to_string(&secret_key).unwrap(),
+            serde_json::to_string(&SecretKey::<Bls12>::from_seed(&[7u8; 32])).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_peks_secret_key_debug_redacted() {
+        let secret_key = SecretKey::<Bls12>::from_seed(&[7u8; 32]);
+        let debug = format!("{:?}", secret_key);
+        assert_eq!(debug, "SecretKey { .. }");
+        assert!(!debug.contains(&secret_key.alpha.to_string()));
+    }
 }
@@ -0,0 +1,83 @@
+use digest::Digest;
+use hmac::Hmac;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use sha2::Sha256;
+
+pub const SEED_SIZE: usize = 32;
+
+/// Number of PBKDF2-HMAC-SHA256 iterations used by `seed_from_passphrase`.
+/// Changing it changes every key derived from a passphrase.
+pub const PASSPHRASE_ITERATIONS: u32 = 100_000;
+
+/// Stretches a passphrase into a seed for the `from_seed` key derivations.
+pub fn seed_from_passphrase(passphrase: &[u8], salt: &[u8]) -> [u8; SEED_SIZE] {
+    let mut seed = [0u8; SEED_SIZE];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase, salt, PASSPHRASE_ITERATIONS, &mut seed);
+    seed
+}
+
+/// A ChaCha20 RNG keyed by `SHA256(domain || seed)`, so that one seed gives independent keys per scheme.
+pub(crate) fn seed_rng(domain: &[u8], seed: &[u8; SEED_SIZE]) -> ChaCha20Rng {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update(seed);
+    let mut rng_seed = [0u8; 32];
+    rng_seed.copy_from_slice(&hasher.finalize());
+    ChaCha20Rng::from_seed(rng_seed)
+}
+
+#[cfg(feature = "c_api")]
+mod c_api {
+    use super::*;
+    use crate::c_utils::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct CSeed {
+        pub bytes: [u8; SEED_SIZE],
+    }
+
+    #[no_mangle]
+    pub extern "C" fn seedFromPassphrase(
+        passphrase: *const u8,
+        passphrase_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        seed: *mut CSeed,
+    ) -> CErrorCode {
+        ffi_output("seedFromPassphrase", seed, || {
+            let passphrase = ptr2bytes(passphrase, passphrase_len)?;
+            let salt = ptr2bytes(salt, salt_len)?;
+            Ok(CSeed {
+                bytes: seed_from_passphrase(passphrase, salt),
+            })
+        })
+    }
+}
+
+#[cfg(feature = "c_api")]
+pub use c_api::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seed_from_passphrase_kat() {
+        // PBKDF2-HMAC-SHA256 with 100000 iterations, as computed by Python's hashlib.
+        let seed = seed_from_passphrase(b"correct horse battery staple", b"rust-searchable-pke");
+        assert_eq!(
+            seed,
+            [
+                0xbc, 0x1b, 0x10, 0xd1, 0x88, 0x38, 0x37, 0xad, 0xeb, 0x4b, 0xf2, 0x42, 0xaa, 0x08,
+                0xdc, 0x08, 0xf2, 0x16, 0x5d, 0xd1, 0xb3, 0xf9, 0x64, 0xf1, 0x24, 0x59, 0x27, 0x0d,
+                0x73, 0x83, 0xa7, 0x09,
+            ]
+        );
+        assert_ne!(
+            seed,
+            seed_from_passphrase(b"correct horse battery staple", b"other salt")
+        );
+    }
+}
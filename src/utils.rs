@@ -1,142 +1,118 @@
 use fff::PrimeField;
 use rayon::prelude::*;
 
-/// References
-/// 1. [vitalik's python implementation of ZK-STARK](https://github.com/ethereum/research/blob/master/mimc_stark/poly_utils.py)
-/// 2. [hrmk1o3's Rust implementation of ZK-STARK](https://github.com/InternetMaximalism/stark-pure-rust/blob/develop/packages/fri/src/poly_utils.rs)
-
+/// Returns the coefficients of `(x - roots[0]) * ... * (x - roots[m - 1])`, lowest degree first.
+/// The factors are multiplied pairwise in a product tree, so the cost is dominated by
+/// the NTT-based multiplications near the root of the tree.
 pub(crate) fn polynomial_from_roots<F: PrimeField>(roots: &[F]) -> Vec<F> {
-    let m = roots.len() + 1;
-
-    let xs = (0..m).fold(Vec::with_capacity(m), |mut vec, i| {
-        if i == 0 {
-            vec.push(F::zero());
-        } else {
-            let mut v = vec[i - 1].clone();
-            v.add_assign(&F::one());
-            vec.push(v);
-        }
-        vec
-    });
-    let root_poly = zpoly(&xs);
-    let numerator_polys = xs
-        .par_iter()
-        .map(|x| {
-            let mut minus_x = F::zero();
-            minus_x.sub_assign(x);
-            div_polys(&root_poly, &[minus_x, F::one()])
+    let mut polys = roots
+        .iter()
+        .map(|root| {
+            let mut minus_root = F::zero();
+            minus_root.sub_assign(root);
+            vec![minus_root, F::one()]
         })
         .collect::<Vec<Vec<F>>>();
-    let denominators = (0..m)
-        .into_par_iter()
-        .map(|i| eval_poly_at(&numerator_polys[i], xs[i]))
-        .collect::<Vec<F>>();
-    let inv_denoms = multi_inv(&denominators);
-
-    let ys = (0..m)
-        .into_par_iter()
-        .map(|i| {
-            let mut y = F::one();
-            for j in 0..(m - 1) {
-                let mut factor = xs[i].clone();
-                factor.sub_assign(&roots[j]);
-                y.mul_assign(&factor);
-            }
-            y
-        })
-        .collect::<Vec<F>>();
-
-    let mut coefficients = vec![F::zero(); m];
-    for i in 0..m {
-        let mut yslice = ys[i];
-        yslice.mul_assign(&inv_denoms[i]);
-        for j in 0..m {
-            let mut term = numerator_polys[i][j].clone();
-            term.mul_assign(&yslice);
-            coefficients[j].add_assign(&term);
-        }
+    if polys.is_empty() {
+        return vec![F::one()];
+    }
+    while polys.len() > 1 {
+        polys = polys
+            .par_chunks(2)
+            .map(|pair| match pair {
+                [a, b] => mul_polys(a, b),
+                _ => pair[0].clone(),
+            })
+            .collect();
     }
-    coefficients
+    polys.pop().unwrap()
 }
 
-fn eval_poly_at<F: PrimeField>(coeffs: &[F], x: F) -> F {
-    let mut result = F::zero();
-    let mut base = F::one();
-    for coeff in coeffs.iter() {
-        let mut term = coeff.clone();
-        term.mul_assign(&base);
-        result.add_assign(&term);
-        base.mul_assign(&x);
+const NTT_THRESHOLD: usize = 32;
+
+fn mul_polys<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    let out_len = a.len() + b.len() - 1;
+    let size = out_len.next_power_of_two();
+    let log_size = size.trailing_zeros();
+    if a.len().min(b.len()) <= NTT_THRESHOLD || log_size > F::S {
+        return mul_polys_naive(a, b);
     }
-    result
-}
 
-// Original: https://github.com/InternetMaximalism/stark-pure-rust/blob/develop/packages/fri/src/poly_utils.rs#L362
-fn zpoly<F: PrimeField>(xs: &[F]) -> Vec<F> {
-    let mut root = vec![F::one()];
-    for i in 0..xs.len() {
-        root.push(F::zero());
-        for j in (0..(i + 1)).rev() {
-            let mut term = root[j];
-            term.mul_assign(&xs[i]);
-            root[j + 1].sub_assign(&term);
-        }
+    // A primitive `size`-th root of unity.
+    let mut omega = F::root_of_unity();
+    for _ in log_size..F::S {
+        omega.square();
+    }
+    let mut a_evals = a.to_vec();
+    a_evals.resize(size, F::zero());
+    let mut b_evals = b.to_vec();
+    b_evals.resize(size, F::zero());
+    ntt(&mut a_evals, omega);
+    ntt(&mut b_evals, omega);
+    for (x, y) in a_evals.iter_mut().zip(b_evals.iter()) {
+        x.mul_assign(y);
+    }
+
+    ntt(&mut a_evals, omega.inverse().unwrap());
+    let mut size_inv = F::one();
+    for _ in 0..log_size {
+        size_inv.double();
     }
-    root.reverse();
-    root
+    let size_inv = size_inv.inverse().unwrap();
+    a_evals.truncate(out_len);
+    for coeff in a_evals.iter_mut() {
+        coeff.mul_assign(&size_inv);
+    }
+    a_evals
 }
 
-fn div_polys<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
-    assert!(a.len() >= b.len());
-    let mut a = a.into_iter().map(|v| v.clone()).collect::<Vec<F>>();
-    let mut outputs = Vec::new();
-    let mut apos = a.len() - 1;
-    let bpos = b.len() - 1;
-    let diff = apos - bpos;
-    for d in (0..(diff + 1)).rev() {
-        let mut quot = a[apos].clone();
-        quot.mul_assign(&b[bpos].inverse().unwrap());
-        outputs.push(quot);
-        for i in (0..bpos + 1).rev() {
-            let mut term = b[i].clone();
-            term.mul_assign(&quot);
-            a[d + i].sub_assign(&term);
+fn mul_polys_naive<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    let mut outputs = vec![F::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            let mut term = *x;
+            term.mul_assign(y);
+            outputs[i + j].add_assign(&term);
         }
-        apos -= 1;
     }
-    outputs.reverse();
     outputs
 }
 
-fn multi_inv<F: PrimeField>(values: &[F]) -> Vec<F> {
-    let mut partials = vec![F::one()];
-    for i in 0..values.len() {
-        let mut v = partials[partials.len() - 1].clone();
-        if values[i] != F::zero() {
-            v.mul_assign(&values[i]);
-        } else {
-            v.mul_assign(&F::one());
+/// In-place radix-2 number theoretic transform.
+/// `values.len()` must be a power of two and `omega` a primitive root of unity of that order.
+fn ntt<F: PrimeField>(values: &mut [F], omega: F) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - log_n);
+        if i < j {
+            values.swap(i, j);
         }
-        partials.push(v)
-    }
-    let mut inv = partials[partials.len() - 1].inverse().unwrap();
-    let mut outputs = vec![F::zero(); values.len()];
-    for i in (0..values.len()).rev() {
-        let mut v = partials[i].clone();
-        outputs[i] = if values[i] != F::zero() {
-            v.mul_assign(&inv);
-            v
-        } else {
-            v.mul_assign(&F::zero());
-            v
-        };
-        if values[i] != F::zero() {
-            inv.mul_assign(&values[i]);
-        } else {
-            inv.mul_assign(&F::one());
-        };
     }
-    outputs
+
+    let mut half = 1;
+    while half < n {
+        let mut omega_step = omega;
+        for _ in 0..(log_n - half.trailing_zeros() - 1) {
+            omega_step.square();
+        }
+        for chunk in values.chunks_mut(2 * half) {
+            let (lo, hi) = chunk.split_at_mut(half);
+            let mut w = F::one();
+            for (x, y) in lo.iter_mut().zip(hi.iter_mut()) {
+                let mut t = *y;
+                t.mul_assign(&w);
+                *y = *x;
+                y.sub_assign(&t);
+                x.add_assign(&t);
+                w.mul_assign(&omega_step);
+            }
+        }
+        half *= 2;
+    }
 }
 
 #[cfg(test)]
@@ -147,6 +123,144 @@ mod test {
     use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
 
+    // The previous O(m^2) Lagrange interpolation, kept as a reference.
+    // References
+    // 1. [vitalik's python implementation of ZK-STARK](https://github.com/ethereum/research/blob/master/mimc_stark/poly_utils.py)
+    // 2. [hrmk1o3's Rust implementation of ZK-STARK](https://github.com/InternetMaximalism/stark-pure-rust/blob/develop/packages/fri/src/poly_utils.rs)
+    fn polynomial_from_roots_by_interpolation<F: PrimeField>(roots: &[F]) -> Vec<F> {
+        let m = roots.len() + 1;
+
+        let xs = (0..m).fold(Vec::with_capacity(m), |mut vec, i| {
+            if i == 0 {
+                vec.push(F::zero());
+            } else {
+                let mut v = vec[i - 1].clone();
+                v.add_assign(&F::one());
+                vec.push(v);
+            }
+            vec
+        });
+        let root_poly = zpoly(&xs);
+        let numerator_polys = xs
+            .par_iter()
+            .map(|x| {
+                let mut minus_x = F::zero();
+                minus_x.sub_assign(x);
+                div_polys(&root_poly, &[minus_x, F::one()])
+            })
+            .collect::<Vec<Vec<F>>>();
+        let denominators = (0..m)
+            .into_par_iter()
+            .map(|i| eval_poly_at(&numerator_polys[i], xs[i]))
+            .collect::<Vec<F>>();
+        let inv_denoms = multi_inv(&denominators);
+
+        let ys = (0..m)
+            .into_par_iter()
+            .map(|i| {
+                let mut y = F::one();
+                for j in 0..(m - 1) {
+                    let mut factor = xs[i].clone();
+                    factor.sub_assign(&roots[j]);
+                    y.mul_assign(&factor);
+                }
+                y
+            })
+            .collect::<Vec<F>>();
+
+        let mut coefficients = vec![F::zero(); m];
+        for i in 0..m {
+            let mut yslice = ys[i];
+            yslice.mul_assign(&inv_denoms[i]);
+            for j in 0..m {
+                let mut term = numerator_polys[i][j].clone();
+                term.mul_assign(&yslice);
+                coefficients[j].add_assign(&term);
+            }
+        }
+        coefficients
+    }
+
+    fn eval_poly_at<F: PrimeField>(coeffs: &[F], x: F) -> F {
+        let mut result = F::zero();
+        let mut base = F::one();
+        for coeff in coeffs.iter() {
+            let mut term = coeff.clone();
+            term.mul_assign(&base);
+            result.add_assign(&term);
+            base.mul_assign(&x);
+        }
+        result
+    }
+
+    // Original: https://github.com/InternetMaximalism/stark-pure-rust/blob/develop/packages/fri/src/poly_utils.rs#L362
+    fn zpoly<F: PrimeField>(xs: &[F]) -> Vec<F> {
+        let mut root = vec![F::one()];
+        for i in 0..xs.len() {
+            root.push(F::zero());
+            for j in (0..(i + 1)).rev() {
+                let mut term = root[j];
+                term.mul_assign(&xs[i]);
+                root[j + 1].sub_assign(&term);
+            }
+        }
+        root.reverse();
+        root
+    }
+
+    fn div_polys<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+        assert!(a.len() >= b.len());
+        let mut a = a.into_iter().map(|v| v.clone()).collect::<Vec<F>>();
+        let mut outputs = Vec::new();
+        let mut apos = a.len() - 1;
+        let bpos = b.len() - 1;
+        let diff = apos - bpos;
+        for d in (0..(diff + 1)).rev() {
+            let mut quot = a[apos].clone();
+            quot.mul_assign(&b[bpos].inverse().unwrap());
+            outputs.push(quot);
+            for i in (0..bpos + 1).rev() {
+                let mut term = b[i].clone();
+                term.mul_assign(&quot);
+                a[d + i].sub_assign(&term);
+            }
+            apos -= 1;
+        }
+        outputs.reverse();
+        outputs
+    }
+
+    fn multi_inv<F: PrimeField>(values: &[F]) -> Vec<F> {
+        let mut partials = vec![F::one()];
+        for i in 0..values.len() {
+            let mut v = partials[partials.len() - 1].clone();
+            if values[i] != F::zero() {
+                v.mul_assign(&values[i]);
+            } else {
+                v.mul_assign(&F::one());
+            }
+            partials.push(v)
+        }
+        let mut inv = partials[partials.len() - 1].inverse().unwrap();
+        let mut outputs = vec![F::zero(); values.len()];
+        for i in (0..values.len()).rev() {
+            let mut v = partials[i].clone();
+            outputs[i] = if values[i] != F::zero() {
+                v.mul_assign(&inv);
+                v
+            } else {
+                v.mul_assign(&F::zero());
+                v
+            };
+            if values[i] != F::zero() {
+                inv.mul_assign(&values[i]);
+            } else {
+                inv.mul_assign(&F::one());
+            };
+        }
+        outputs
+    }
+
     #[test]
     fn degree_one() {
         let mut rng = <XorShiftRng as SeedableRng>::from_seed([
@@ -213,4 +327,33 @@ mod test {
             assert_eq!(sum, Fr::zero());
         }
     }
+
+    #[test]
+    fn matches_interpolation() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        for n in (0..80).chain([255, 256, 257, 1000]) {
+            let roots = (0..n).map(|_| Fr::random(&mut rng)).collect::<Vec<Fr>>();
+            assert_eq!(
+                polynomial_from_roots(&roots),
+                polynomial_from_roots_by_interpolation(&roots)
+            );
+        }
+    }
+
+    #[test]
+    fn matches_interpolation_large() {
+        let mut rng = <XorShiftRng as SeedableRng>::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let n = 4100;
+        let roots = (0..n).map(|_| Fr::random(&mut rng)).collect::<Vec<Fr>>();
+        assert_eq!(
+            polynomial_from_roots(&roots),
+            polynomial_from_roots_by_interpolation(&roots)
+        );
+    }
 }
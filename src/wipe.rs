@@ -0,0 +1,11 @@
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// Overwrites `values` with `zero` through volatile writes, which the compiler cannot elide
+/// even though the values are about to be dropped.
+pub(crate) fn wipe<T: Copy>(values: &mut [T], zero: T) {
+    for value in values.iter_mut() {
+        unsafe { ptr::write_volatile(value, zero) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,208 @@
+use crate::EngineId;
+use fff::{PrimeField, PrimeFieldDecodingError, PrimeFieldRepr};
+use groupy::{CurveAffine, EncodedPoint, GroupDecodingError};
+use paired::{Compress, Engine};
+use std::convert::TryFrom;
+use std::io::{self, Read};
+use thiserror::Error;
+
+/// Layout of the header shared by every binary encoding in this crate.
+///
+/// | bytes | content                    |
+/// |-------|----------------------------|
+/// | 4     | magic `b"RSPK"`            |
+/// | 1     | format version             |
+/// | 1     | scheme id                  |
+/// | 1     | curve id                   |
+/// | 1     | object kind                |
+/// | 4     | keyword count (big endian) |
+const MAGIC: [u8; 4] = *b"RSPK";
+const VERSION: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum WireError {
+    #[error("The given bytes do not start with the magic bytes")]
+    InvalidMagic,
+    #[error("The format version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("The scheme id is {0}, but {1} is expected")]
+    SchemeMismatch(u8, u8),
+    #[error("The curve id is {0}, but {1} is expected")]
+    CurveMismatch(u8, u8),
+    #[error("The object kind is {0}, but {1} is expected")]
+    KindMismatch(u8, u8),
+    #[error("The tag {0} is invalid")]
+    InvalidTag(u8),
+    #[error("The number {0} does not fit in the encoding")]
+    TooLarge(usize),
+    #[error("The count {0} exceeds the {1} bytes left to decode")]
+    CountOutOfRange(u32, usize),
+    #[error("The {0} has {1} entries, but {2} are expected")]
+    LengthMismatch(&'static str, usize, usize),
+    #[error("{0} bytes remain after decoding")]
+    TrailingBytes(usize),
+    #[error(transparent)]
+    GroupDecode(#[from] GroupDecodingError),
+    #[error(transparent)]
+    PrimeDecode(#[from] PrimeFieldDecodingError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SchemeId {
+    Pecdk = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ObjectKind {
+    SecretKey = 1,
+    PublicKey = 2,
+    Ciphertext = 3,
+    Trapdoor = 4,
+}
+
+pub(crate) fn write_header<E: EngineId>(
+    out: &mut Vec<u8>,
+    scheme: SchemeId,
+    kind: ObjectKind,
+    num_keyword: usize,
+) -> Result<(), WireError> {
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.push(scheme as u8);
+    out.push(E::CURVE_ID);
+    out.push(kind as u8);
+    write_len(out, num_keyword)
+}
+
+/// Checks the header and returns the keyword count recorded in it.
+pub(crate) fn read_header<E: EngineId>(
+    reader: &mut &[u8],
+    scheme: SchemeId,
+    kind: ObjectKind,
+) -> Result<usize, WireError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(WireError::InvalidMagic);
+    }
+    let version = read_u8(reader)?;
+    if version != VERSION {
+        return Err(WireError::UnsupportedVersion(version));
+    }
+    let read_scheme = read_u8(reader)?;
+    if read_scheme != scheme as u8 {
+        return Err(WireError::SchemeMismatch(read_scheme, scheme as u8));
+    }
+    let curve = read_u8(reader)?;
+    if curve != E::CURVE_ID {
+        return Err(WireError::CurveMismatch(curve, E::CURVE_ID));
+    }
+    let read_kind = read_u8(reader)?;
+    if read_kind != kind as u8 {
+        return Err(WireError::KindMismatch(read_kind, kind as u8));
+    }
+    // Every object stores at least `count + 1` non-empty items after the header,
+    // so a larger count is malformed and `count + 1` can never overflow `usize`.
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    let count = u32::from_be_bytes(bytes);
+    match usize::try_from(count) {
+        Ok(len) if len < reader.len() => Ok(len),
+        _ => Err(WireError::CountOutOfRange(count, reader.len())),
+    }
+}
+
+/// Checks that a vector about to be encoded has the length implied by the header.
+pub(crate) fn check_len(what: &'static str, len: usize, expected: usize) -> Result<(), WireError> {
+    if len == expected {
+        Ok(())
+    } else {
+        Err(WireError::LengthMismatch(what, len, expected))
+    }
+}
+
+pub(crate) fn finish(reader: &[u8]) -> Result<(), WireError> {
+    if reader.is_empty() {
+        Ok(())
+    } else {
+        Err(WireError::TrailingBytes(reader.len()))
+    }
+}
+
+pub(crate) fn read_u8(reader: &mut &[u8]) -> Result<u8, WireError> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+pub(crate) fn write_len(out: &mut Vec<u8>, len: usize) -> Result<(), WireError> {
+    let len = u32::try_from(len).map_err(|_| WireError::TooLarge(len))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+pub(crate) fn read_len(reader: &mut &[u8]) -> Result<usize, WireError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    let len = u32::from_be_bytes(bytes);
+    usize::try_from(len).map_err(|_| WireError::CountOutOfRange(len, reader.len()))
+}
+
+pub(crate) fn write_fr<F: PrimeField>(out: &mut Vec<u8>, field: &F) -> Result<(), WireError> {
+    field.into_repr().write_be(out)?;
+    Ok(())
+}
+
+pub(crate) fn read_fr<F: PrimeField>(reader: &mut &[u8]) -> Result<F, WireError> {
+    let mut repr = F::Repr::default();
+    repr.read_be(reader)?;
+    Ok(F::from_repr(repr)?)
+}
+
+pub(crate) fn write_point<C: CurveAffine>(out: &mut Vec<u8>, point: &C) {
+    out.extend_from_slice(point.into_compressed().as_ref());
+}
+
+pub(crate) fn read_point<C: CurveAffine>(reader: &mut &[u8]) -> Result<C, WireError> {
+    let mut compressed = C::Compressed::empty();
+    reader.read_exact(compressed.as_mut())?;
+    Ok(compressed.into_affine()?)
+}
+
+pub(crate) fn write_fqk<E: Engine>(out: &mut Vec<u8>, field: E::Fqk) -> Result<(), WireError> {
+    field.write_compressed(out)?;
+    Ok(())
+}
+
+pub(crate) fn read_fqk<E: Engine>(reader: &mut &[u8]) -> Result<E::Fqk, WireError> {
+    Ok(E::Fqk::read_compressed(reader)?)
+}
+
+pub(crate) fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), WireError> {
+    write_len(out, bytes.len())?;
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+pub(crate) fn read_bytes(reader: &mut &[u8]) -> Result<Vec<u8>, WireError> {
+    let len = read_len(reader)?;
+    if len > reader.len() {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes.to_vec())
+}
+
+pub(crate) fn read_points<C: CurveAffine>(
+    reader: &mut &[u8],
+    len: usize,
+) -> Result<Vec<C>, WireError> {
+    (0..len).map(|_| read_point(reader)).collect()
+}
+
+pub(crate) fn read_frs<F: PrimeField>(reader: &mut &[u8], len: usize) -> Result<Vec<F>, WireError> {
+    (0..len).map(|_| read_fr(reader)).collect()
+}